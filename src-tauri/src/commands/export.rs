@@ -1,4 +1,5 @@
 use crate::db::invoice_repo::{self, InvoiceFilter};
+use crate::models::company::BuyerCheck;
 use crate::models::invoice::InvoiceType;
 use crate::services::export::ExportService;
 use serde::{Deserialize, Serialize};
@@ -38,6 +39,7 @@ pub fn export_all_invoices(
     date_to: Option<String>,
     keyword: Option<String>,
     category: Option<String>,
+    buyer_check: Option<String>,
 ) -> Result<ExportResult, String> {
     let filter = InvoiceFilter {
         invoice_type: invoice_type.map(|s| InvoiceType::from_str(&s)),
@@ -47,6 +49,7 @@ pub fn export_all_invoices(
         amount_max: None,
         keyword,
         category,
+        buyer_check: buyer_check.and_then(|s| BuyerCheck::parse(&s)),
     };
 
     let invoices = invoice_repo::find_all_for_export(filter).map_err(|e| e.to_string())?;
@@ -1,3 +1,5 @@
+use crate::models::attachment::AttachmentContent;
+use crate::services::attachment::AttachmentStore;
 use crate::services::file::{FileInfo, FileService};
 use std::path::Path;
 
@@ -20,3 +22,11 @@ pub fn get_file_base64(file_path: String) -> Result<String, String> {
 pub fn get_supported_extensions() -> Vec<&'static str> {
     FileService::supported_extensions()
 }
+
+/// 获取归档的原始文件
+#[tauri::command]
+pub fn get_attachment(hash: String) -> Result<AttachmentContent, String> {
+    AttachmentStore::new()
+        .and_then(|store| store.read(&hash))
+        .map_err(|e| e.to_string())
+}
@@ -1,6 +1,14 @@
 use crate::db::invoice_repo;
+use crate::models::company::BuyerCheck;
 use crate::models::invoice::{Invoice, InvoiceType};
+use crate::models::ocr_response::parse_date;
+use crate::models::warning::InvoiceWarning;
+use crate::services::attachment::AttachmentStore;
+use crate::services::buyer_check::BuyerChecker;
+use crate::services::file::FileService;
 use crate::services::invoice::{InvoiceService, RecognizeResult};
+use crate::services::preprocess::PreprocessOptions;
+use crate::services::validation::InvoiceValidator;
 
 /// 获取发票详情
 #[tauri::command]
@@ -11,13 +19,29 @@ pub fn get_invoice(id: String) -> Result<Option<Invoice>, String> {
 /// 删除发票
 #[tauri::command]
 pub fn delete_invoice(id: String) -> Result<bool, String> {
-    invoice_repo::delete(&id).map_err(|e| e.to_string())
+    let deleted = invoice_repo::delete(&id).map_err(|e| e.to_string())?;
+    collect_garbage();
+    Ok(deleted)
 }
 
 /// 批量删除发票
 #[tauri::command]
 pub fn delete_invoices(ids: Vec<String>) -> Result<u32, String> {
-    invoice_repo::delete_batch(&ids).map_err(|e| e.to_string())
+    let deleted = invoice_repo::delete_batch(&ids).map_err(|e| e.to_string())?;
+    collect_garbage();
+    Ok(deleted)
+}
+
+/// 删除发票后清理不再被引用的原始文件和预处理图片
+///
+/// 发票已删除成功，清理失败不影响结果，剩下的文件在下次删除时再清理。
+fn collect_garbage() {
+    AttachmentStore::new()
+        .and_then(|store| store.collect_garbage())
+        .ok();
+    invoice_repo::find_processed_file_paths()
+        .and_then(|referenced| FileService::collect_processed(&referenced))
+        .ok();
 }
 
 /// 识别发票
@@ -25,8 +49,11 @@ pub fn delete_invoices(ids: Vec<String>) -> Result<u32, String> {
 pub async fn recognize_invoice(
     file_path: String,
     invoice_type: Option<String>,
+    preprocess: Option<PreprocessOptions>,
 ) -> Result<Invoice, String> {
-    let service = InvoiceService::new();
+    let service = InvoiceService::new()
+        .map_err(|e| e.to_string())?
+        .with_preprocess(preprocess.unwrap_or_default());
     let inv_type = invoice_type.map(|s| InvoiceType::from_str(&s));
     service
         .recognize_invoice(&file_path, inv_type)
@@ -34,18 +61,24 @@ pub async fn recognize_invoice(
         .map_err(|e| e.to_string())
 }
 
-/// 识别并保存发票
+/// 识别并保存发票（`split_multiple` 为 true 时一图多票拆分保存），重复发票按配置处理，
+/// 返回保存、重复和购买方不符的完整结果
 #[tauri::command]
 pub async fn recognize_and_save_invoice(
     file_path: String,
     invoice_type: Option<String>,
-) -> Result<Invoice, String> {
-    let service = InvoiceService::new();
+    split_multiple: Option<bool>,
+    preprocess: Option<PreprocessOptions>,
+) -> Result<RecognizeResult, String> {
+    let service = InvoiceService::new()
+        .map_err(|e| e.to_string())?
+        .with_preprocess(preprocess.unwrap_or_default());
     let inv_type = invoice_type.map(|s| InvoiceType::from_str(&s));
-    service
-        .recognize_and_save(&file_path, inv_type)
+    let saved = service
+        .recognize_and_save(&file_path, inv_type, split_multiple.unwrap_or(false))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    Ok(RecognizeResult::saved(&file_path, saved))
 }
 
 /// 批量识别发票
@@ -53,31 +86,67 @@ pub async fn recognize_and_save_invoice(
 pub async fn recognize_invoices_batch(
     file_paths: Vec<String>,
     invoice_type: Option<String>,
+    split_multiple: Option<bool>,
+    preprocess: Option<PreprocessOptions>,
 ) -> Result<Vec<RecognizeResult>, String> {
-    let service = InvoiceService::new();
+    let service = InvoiceService::new()
+        .map_err(|e| e.to_string())?
+        .with_preprocess(preprocess.unwrap_or_default());
     let inv_type = invoice_type.map(|s| InvoiceType::from_str(&s));
-    Ok(service.recognize_batch(&file_paths, inv_type).await)
+    Ok(service
+        .recognize_batch(&file_paths, inv_type, split_multiple.unwrap_or(false))
+        .await)
 }
 
 /// 测试 OCR 连接
 #[tauri::command]
-pub async fn test_ocr_connection(
-    api_key: String,
-    secret_key: String,
-) -> Result<bool, String> {
-    let service = InvoiceService::new();
-    service
-        .test_connection(&api_key, &secret_key)
+pub async fn test_ocr_connection(api_key: String, secret_key: String) -> Result<bool, String> {
+    InvoiceService::test_connection(&api_key, &secret_key)
         .await
         .map_err(|e| e.to_string())
 }
 
 /// 更新发票
 #[tauri::command]
-pub fn update_invoice(invoice: Invoice) -> Result<(), String> {
+pub fn update_invoice(mut invoice: Invoice) -> Result<(), String> {
+    // 手动填写的日期同样统一为 YYYY-MM-DD
+    if let Some(date) = invoice
+        .invoice_date
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+    {
+        invoice.invoice_date =
+            Some(parse_date(Some(date)).ok_or_else(|| format!("开票日期无效: {}", date))?);
+        InvoiceWarning::remove_from(&mut invoice.warnings, |w| {
+            matches!(w, InvoiceWarning::InvalidDate { .. })
+        });
+    }
+    // 手动修改金额、购买方后重新校验
+    InvoiceValidator::apply(&mut invoice);
+    BuyerChecker::from_config()
+        .map_err(|e| e.to_string())?
+        .apply(&mut invoice);
     invoice_repo::update(&invoice).map_err(|e| e.to_string())
 }
 
+/// 修改公司主体配置后，重新核对全部发票的购买方，返回结果有变化的数量
+#[tauri::command]
+pub fn recheck_buyers() -> Result<u32, String> {
+    let checker = BuyerChecker::from_config().map_err(|e| e.to_string())?;
+    let invoices =
+        invoice_repo::find_all_for_export(Default::default()).map_err(|e| e.to_string())?;
+    let mut changed = 0;
+    for mut invoice in invoices {
+        let previous = invoice.buyer_check;
+        checker.apply(&mut invoice);
+        if invoice.buyer_check != previous {
+            invoice_repo::update(&invoice).map_err(|e| e.to_string())?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
 /// 获取发票列表
 #[tauri::command]
 pub fn get_invoices(
@@ -87,6 +156,7 @@ pub fn get_invoices(
     date_from: Option<String>,
     date_to: Option<String>,
     keyword: Option<String>,
+    buyer_check: Option<String>,
 ) -> Result<crate::db::invoice_repo::PagedResult<Invoice>, String> {
     let filter = crate::db::invoice_repo::InvoiceFilter {
         invoice_type: invoice_type.map(|s| InvoiceType::from_str(&s)),
@@ -96,6 +166,7 @@ pub fn get_invoices(
         amount_max: None,
         keyword,
         category: None,
+        buyer_check: buyer_check.and_then(|s| BuyerCheck::parse(&s)),
     };
     let pagination = crate::db::invoice_repo::Pagination { page, page_size };
     invoice_repo::find_all(filter, pagination).map_err(|e| e.to_string())
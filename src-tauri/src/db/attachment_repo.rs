@@ -0,0 +1,66 @@
+use rusqlite::{params, Connection};
+
+use crate::error::AppResult;
+use crate::models::attachment::Attachment;
+
+/// 插入附件记录，相同内容已存在时保留原记录
+///
+/// 由调用方传入连接，以便与写附件文件、保存发票放在同一事务中
+pub fn insert_if_absent(conn: &Connection, attachment: &Attachment) -> AppResult<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO attachments (hash, size, mime_type, file_name, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            attachment.hash,
+            attachment.size as i64,
+            attachment.mime_type,
+            attachment.file_name,
+            attachment.created_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// 按哈希查询
+pub fn find_by_hash(hash: &str) -> AppResult<Option<Attachment>> {
+    let conn = super::get_connection()?;
+    let mut stmt = conn.prepare(
+        "SELECT hash, size, mime_type, file_name, created_at FROM attachments WHERE hash = ?",
+    )?;
+
+    let result = stmt.query_row(params![hash], |row| {
+        Ok(Attachment {
+            hash: row.get(0)?,
+            size: row.get::<_, i64>(1)? as u64,
+            mime_type: row.get(2)?,
+            file_name: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    });
+
+    match result {
+        Ok(attachment) => Ok(Some(attachment)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 删除没有发票引用的附件记录，返回被删除的哈希
+///
+/// 由调用方传入连接，以便在持有连接期间一并删除附件文件
+pub fn delete_unreferenced(conn: &Connection) -> AppResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT hash FROM attachments WHERE hash NOT IN (
+            SELECT attachment_hash FROM invoices WHERE attachment_hash IS NOT NULL
+        )",
+    )?;
+    let hashes: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    for hash in &hashes {
+        conn.execute("DELETE FROM attachments WHERE hash = ?", params![hash])?;
+    }
+    Ok(hashes)
+}
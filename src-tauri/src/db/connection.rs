@@ -8,8 +8,8 @@ use crate::error::{AppError, AppResult};
 /// 全局数据库连接
 static DB_CONNECTION: OnceCell<Mutex<Connection>> = OnceCell::new();
 
-/// 获取数据库文件路径
-fn get_db_path() -> AppResult<PathBuf> {
+/// 获取应用数据目录（不存在时创建）
+pub fn app_data_dir() -> AppResult<PathBuf> {
     let data_dir = dirs::data_local_dir()
         .ok_or_else(|| AppError::Config("无法获取用户数据目录".to_string()))?;
 
@@ -20,7 +20,12 @@ fn get_db_path() -> AppResult<PathBuf> {
         std::fs::create_dir_all(&app_dir)?;
     }
 
-    Ok(app_dir.join("data.db"))
+    Ok(app_dir)
+}
+
+/// 获取数据库文件路径
+fn get_db_path() -> AppResult<PathBuf> {
+    Ok(app_data_dir()?.join("data.db"))
 }
 
 /// 初始化数据库连接
@@ -50,6 +55,29 @@ pub fn get_connection() -> AppResult<std::sync::MutexGuard<'static, Connection>>
         .map_err(|_| AppError::Database(rusqlite::Error::InvalidQuery))
 }
 
+/// 在一个事务中执行 `f`，返回错误时整体回滚
+///
+/// 事务期间持有全局连接，`f` 中只能使用传入的连接，不能再调用 `get_connection`。
+pub fn with_transaction<T>(f: impl FnOnce(&Connection) -> AppResult<T>) -> AppResult<T> {
+    let mut conn = get_connection()?;
+    let tx = conn.transaction()?;
+    let result = f(&tx)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+/// 初始化内存数据库（仅测试使用，多次调用共享同一连接）
+#[cfg(test)]
+pub fn init_test_database() -> AppResult<()> {
+    DB_CONNECTION.get_or_try_init(|| {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        super::schema::run_migrations(&conn)?;
+        Ok::<_, AppError>(Mutex::new(conn))
+    })?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,26 +1,35 @@
-use rusqlite::params;
+use rusqlite::{params, Connection, ErrorCode};
+use std::collections::HashSet;
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::models::company::BuyerCheck;
 use crate::models::invoice::{Invoice, InvoiceType};
+use crate::models::money::Money;
 
 pub use super::types::{InvoiceFilter, PagedResult, Pagination};
 
 /// 插入发票
 pub fn insert(invoice: &Invoice) -> AppResult<()> {
-    let conn = super::get_connection()?;
+    insert_in(&*super::get_connection()?, invoice)
+}
 
-    conn.execute(
+/// 在指定连接（如事务）上插入发票
+pub fn insert_in(conn: &Connection, invoice: &Invoice) -> AppResult<()> {
+    let result = conn.execute(
         "INSERT INTO invoices (
             id, invoice_type, invoice_code, invoice_number, invoice_date,
             amount_without_tax, tax_amount, total_amount,
             buyer_name, buyer_tax_number, seller_name, seller_tax_number,
             commodity_name, commodity_detail, check_code, machine_code,
             original_file_path, file_type, ocr_raw_response, ocr_confidence,
-            category, remark, is_verified, created_at, updated_at
+            category, remark, is_verified, created_at, updated_at, extra_data,
+            classified_by, bounding_box, page_number, warnings,
+            processed_file_path, attachment_hash, duplicate_of, buyer_check
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
             ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
-            ?21, ?22, ?23, ?24, ?25
+            ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
+            ?31, ?32, ?33, ?34
         )",
         params![
             invoice.id,
@@ -48,15 +57,53 @@ pub fn insert(invoice: &Invoice) -> AppResult<()> {
             invoice.is_verified as i32,
             invoice.created_at,
             invoice.updated_at,
+            invoice.extra_data,
+            invoice.classified_by,
+            invoice.bounding_box,
+            invoice.page_number,
+            invoice.warnings,
+            invoice.processed_file_path,
+            invoice.attachment_hash,
+            invoice.duplicate_of,
+            invoice.buyer_check.map(|check| check.as_str()),
         ],
-    )?;
+    );
 
+    result.map_err(|e| to_duplicate_error(conn, invoice, e))?;
     Ok(())
 }
 
+/// 违反发票代码 + 号码唯一约束时转换为 `AppError::Duplicate`
+fn to_duplicate_error(conn: &Connection, invoice: &Invoice, err: rusqlite::Error) -> AppError {
+    let is_unique_violation = matches!(
+        &err,
+        rusqlite::Error::SqliteFailure(e, _)
+            if e.code == ErrorCode::ConstraintViolation
+                && e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+    );
+    if !is_unique_violation {
+        return err.into();
+    }
+
+    let existing_id = conn.query_row(
+        "SELECT id FROM invoices
+         WHERE invoice_code = ?1 AND invoice_number = ?2 AND duplicate_of IS NULL AND id != ?3",
+        params![invoice.invoice_code, invoice.invoice_number, invoice.id],
+        |row| row.get(0),
+    );
+    match existing_id {
+        Ok(existing_id) => AppError::Duplicate { existing_id },
+        Err(_) => err.into(),
+    }
+}
+
 /// 按 ID 查询
 pub fn find_by_id(id: &str) -> AppResult<Option<Invoice>> {
-    let conn = super::get_connection()?;
+    find_by_id_in(&*super::get_connection()?, id)
+}
+
+/// 在指定连接（如事务）上按 ID 查询
+pub fn find_by_id_in(conn: &Connection, id: &str) -> AppResult<Option<Invoice>> {
     let mut stmt = conn.prepare("SELECT * FROM invoices WHERE id = ?")?;
 
     let result = stmt.query_row(params![id], row_to_invoice);
@@ -68,6 +115,61 @@ pub fn find_by_id(id: &str) -> AppResult<Option<Invoice>> {
     }
 }
 
+/// 按发票代码 + 号码查询已有发票 ID（无代码的发票只比较号码）
+pub fn find_id_by_code_number(code: Option<&str>, number: &str) -> AppResult<Option<String>> {
+    query_id(
+        "SELECT id FROM invoices
+         WHERE invoice_number = ?1 AND IFNULL(invoice_code, '') = IFNULL(?2, '')
+         ORDER BY duplicate_of IS NOT NULL, created_at
+         LIMIT 1",
+        params![number, code],
+    )
+}
+
+/// 按原始文件哈希、页码和票据位置查询已有发票 ID
+///
+/// 一图多票拆分出的发票页码相同，靠票据位置区分。
+pub fn find_id_by_attachment(
+    hash: &str,
+    page_number: Option<u32>,
+    bounding_box: Option<&str>,
+) -> AppResult<Option<String>> {
+    query_id(
+        "SELECT id FROM invoices
+         WHERE attachment_hash = ?1 AND IFNULL(page_number, 0) = IFNULL(?2, 0)
+           AND IFNULL(bounding_box, '') = IFNULL(?3, '')
+         LIMIT 1",
+        params![hash, page_number, bounding_box],
+    )
+}
+
+/// 按销售方、开票日期和价税合计查询已有发票 ID
+///
+/// 传入 `invoice_number` 时只匹配没有号码的记录：两张号码不同的发票不是重复。
+pub fn find_id_by_seller_date_amount(
+    seller_name: &str,
+    invoice_date: &str,
+    total_amount: Money,
+    invoice_number: Option<&str>,
+) -> AppResult<Option<String>> {
+    query_id(
+        "SELECT id FROM invoices
+         WHERE seller_name = ?1 AND invoice_date = ?2 AND total_amount = ?3
+           AND (?4 IS NULL OR IFNULL(TRIM(invoice_number), '') = '')
+         LIMIT 1",
+        params![seller_name, invoice_date, total_amount, invoice_number],
+    )
+}
+
+fn query_id(sql: &str, params: impl rusqlite::Params) -> AppResult<Option<String>> {
+    let conn = super::get_connection()?;
+    match conn.query_row(sql, params, |row| row.get(0)) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// 查询发票列表（分页+筛选）
 pub fn find_all(filter: InvoiceFilter, pagination: Pagination) -> AppResult<PagedResult<Invoice>> {
     let conn = super::get_connection()?;
@@ -102,16 +204,21 @@ pub fn find_all(filter: InvoiceFilter, pagination: Pagination) -> AppResult<Page
 
 /// 更新发票
 pub fn update(invoice: &Invoice) -> AppResult<()> {
-    let conn = super::get_connection()?;
+    update_in(&*super::get_connection()?, invoice)
+}
+
+/// 在指定连接（如事务）上更新发票
+pub fn update_in(conn: &Connection, invoice: &Invoice) -> AppResult<()> {
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
+    let result = conn.execute(
         "UPDATE invoices SET
             invoice_type = ?2, invoice_code = ?3, invoice_number = ?4, invoice_date = ?5,
             amount_without_tax = ?6, tax_amount = ?7, total_amount = ?8,
             buyer_name = ?9, buyer_tax_number = ?10, seller_name = ?11, seller_tax_number = ?12,
             commodity_name = ?13, commodity_detail = ?14, check_code = ?15, machine_code = ?16,
-            category = ?17, remark = ?18, is_verified = ?19, updated_at = ?20
+            category = ?17, remark = ?18, is_verified = ?19, updated_at = ?20,
+            extra_data = ?21, warnings = ?22, buyer_check = ?23
         WHERE id = ?1",
         params![
             invoice.id,
@@ -134,12 +241,29 @@ pub fn update(invoice: &Invoice) -> AppResult<()> {
             invoice.remark,
             invoice.is_verified as i32,
             now,
+            invoice.extra_data,
+            invoice.warnings,
+            invoice.buyer_check.map(|check| check.as_str()),
         ],
-    )?;
+    );
+
+    result.map_err(|e| to_duplicate_error(conn, invoice, e))?;
 
     Ok(())
 }
 
+/// 所有发票引用的预处理图片路径
+pub fn find_processed_file_paths() -> AppResult<HashSet<String>> {
+    let conn = super::get_connection()?;
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT processed_file_path FROM invoices WHERE processed_file_path IS NOT NULL",
+    )?;
+    let paths = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<HashSet<String>, _>>()?;
+    Ok(paths)
+}
+
 /// 删除发票
 pub fn delete(id: &str) -> AppResult<bool> {
     let conn = super::get_connection()?;
@@ -231,6 +355,10 @@ fn build_where_clause(filter: &InvoiceFilter) -> (String, Vec<String>) {
         conditions.push("category = ?".to_string());
         params.push(category.clone());
     }
+    if let Some(buyer_check) = filter.buyer_check {
+        conditions.push("buyer_check = ?".to_string());
+        params.push(buyer_check.as_str().to_string());
+    }
 
     let where_clause = if conditions.is_empty() {
         String::new()
@@ -260,12 +388,23 @@ fn row_to_invoice(row: &rusqlite::Row) -> rusqlite::Result<Invoice> {
         seller_tax_number: row.get("seller_tax_number")?,
         commodity_name: row.get("commodity_name")?,
         commodity_detail: row.get("commodity_detail")?,
+        extra_data: row.get("extra_data")?,
         check_code: row.get("check_code")?,
         machine_code: row.get("machine_code")?,
         original_file_path: row.get("original_file_path")?,
+        processed_file_path: row.get("processed_file_path")?,
+        attachment_hash: row.get("attachment_hash")?,
+        duplicate_of: row.get("duplicate_of")?,
+        buyer_check: row
+            .get::<_, Option<String>>("buyer_check")?
+            .and_then(|check| BuyerCheck::parse(&check)),
+        bounding_box: row.get("bounding_box")?,
+        page_number: row.get("page_number")?,
+        warnings: row.get("warnings")?,
         file_type: row.get("file_type")?,
         ocr_raw_response: row.get("ocr_raw_response")?,
         ocr_confidence: row.get("ocr_confidence")?,
+        classified_by: row.get("classified_by")?,
         category: row.get("category")?,
         remark: row.get("remark")?,
         is_verified: is_verified != 0,
@@ -273,3 +412,61 @@ fn row_to_invoice(row: &rusqlite::Row) -> rusqlite::Result<Invoice> {
         updated_at: row.get("updated_at")?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_duplicate_code_number() {
+        super::super::init_test_database().unwrap();
+        let number = uuid::Uuid::new_v4().simple().to_string();
+        let new_invoice = |code: Option<&str>| {
+            let mut invoice = Invoice::new(InvoiceType::VatInvoice, Money::from_fen(10000));
+            invoice.invoice_code = code.map(String::from);
+            invoice.invoice_number = Some(number.clone());
+            invoice
+        };
+
+        let first = new_invoice(Some("044001900111"));
+        insert(&first).unwrap();
+
+        let err = insert(&new_invoice(Some("044001900111"))).unwrap_err();
+        assert!(matches!(err, AppError::Duplicate { existing_id } if existing_id == first.id));
+
+        // 标记为副本或缺少代码的记录不受约束
+        let mut copy = new_invoice(Some("044001900111"));
+        copy.duplicate_of = Some(first.id.clone());
+        insert(&copy).unwrap();
+        insert(&new_invoice(None)).unwrap();
+        insert(&new_invoice(None)).unwrap();
+    }
+
+    #[test]
+    fn test_update_stores_warnings() {
+        use crate::models::warning::InvoiceWarning;
+        use crate::services::validation::InvoiceValidator;
+
+        super::super::init_test_database().unwrap();
+        let mut invoice = Invoice::new(InvoiceType::VatInvoice, Money::from_fen(113000));
+        invoice.amount_without_tax = Some(Money::from_fen(100000));
+        invoice.tax_amount = Some(Money::from_fen(13000));
+        insert(&invoice).unwrap();
+
+        // 手动改错税额后重新校验，提示随更新写入
+        invoice.tax_amount = Some(Money::from_fen(12000));
+        InvoiceValidator::apply(&mut invoice);
+        update(&invoice).unwrap();
+        let stored = find_by_id(&invoice.id).unwrap().unwrap();
+        assert!(matches!(
+            InvoiceWarning::list_from_json(stored.warnings.as_deref())[..],
+            [InvoiceWarning::AmountMismatch { .. }]
+        ));
+
+        // 改回正确金额后提示清空
+        invoice.tax_amount = Some(Money::from_fen(13000));
+        InvoiceValidator::apply(&mut invoice);
+        update(&invoice).unwrap();
+        assert_eq!(find_by_id(&invoice.id).unwrap().unwrap().warnings, None);
+    }
+}
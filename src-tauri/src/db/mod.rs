@@ -1,9 +1,12 @@
+pub mod attachment_repo;
 pub mod config_repo;
 pub mod connection;
 pub mod invoice_repo;
 pub mod schema;
 pub mod types;
 
-pub use connection::{get_connection, init_database};
+pub use connection::{app_data_dir, get_connection, init_database, with_transaction};
+#[cfg(test)]
+pub use connection::init_test_database;
 pub use schema::run_migrations;
 pub use types::{InvoiceFilter, PagedResult, Pagination};
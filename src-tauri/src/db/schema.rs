@@ -1,9 +1,119 @@
-use rusqlite::Connection;
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, Connection};
 
 use crate::error::AppResult;
 
-/// 运行数据库迁移
+/// 一次数据库结构变更
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: fn(&Connection) -> AppResult<()>,
+}
+
+/// 全部迁移，按版本号递增排列；已发布的迁移不能再修改，结构变更只能追加新版本
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "初始结构",
+        up: migrate_v1_initial,
+    },
+    Migration {
+        version: 2,
+        description: "识别元数据（票种扩展、分类器、位置、页码、提示）",
+        up: migrate_v2_recognition_metadata,
+    },
+    Migration {
+        version: 3,
+        description: "预处理图片路径",
+        up: migrate_v3_processed_file_path,
+    },
+    Migration {
+        version: 4,
+        description: "原始文件归档",
+        up: migrate_v4_attachments,
+    },
+    Migration {
+        version: 5,
+        description: "发票代码 + 号码唯一约束",
+        up: migrate_v5_unique_code_number,
+    },
+    Migration {
+        version: 6,
+        description: "金额改为以分为单位的整数",
+        up: migrate_v6_money_in_fen,
+    },
+    Migration {
+        version: 7,
+        description: "购买方核对结果",
+        up: migrate_v7_buyer_check,
+    },
+    Migration {
+        version: 8,
+        description: "开票日期统一为 YYYY-MM-DD",
+        up: migrate_v8_normalize_invoice_dates,
+    },
+];
+
+/// 运行数据库迁移，升级到最新版本
 pub fn run_migrations(conn: &Connection) -> AppResult<()> {
+    migrate_to(conn, latest_version())
+}
+
+/// 最新的结构版本
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// 当前数据库的结构版本（未记录版本的数据库为 0）
+pub fn current_version(conn: &Connection) -> AppResult<u32> {
+    create_schema_version_table(conn)?;
+    let version = conn.query_row(
+        "SELECT IFNULL(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+/// 依次应用未执行的迁移直到 `target` 版本，每个迁移在独立事务中执行
+fn migrate_to(conn: &Connection, target: u32) -> AppResult<()> {
+    let current = current_version(conn)?;
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|m| m.version > current && m.version <= target)
+    {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            params![
+                migration.version,
+                migration.description,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn create_schema_version_table(conn: &Connection) -> AppResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v1：发票表、配置表、索引和统计视图
+///
+/// 引入版本记录之前创建的数据库版本为 0，会从这里开始执行，
+/// 因此 v1 使用 `IF NOT EXISTS`，后续补列的迁移也要容忍列已存在。
+fn migrate_v1_initial(conn: &Connection) -> AppResult<()> {
     create_invoices_table(conn)?;
     create_configs_table(conn)?;
     create_indexes(conn)?;
@@ -11,7 +121,195 @@ pub fn run_migrations(conn: &Connection) -> AppResult<()> {
     Ok(())
 }
 
-/// 创建发票表
+fn migrate_v2_recognition_metadata(conn: &Connection) -> AppResult<()> {
+    add_column_if_missing(conn, "invoices", "extra_data", "TEXT")?;
+    add_column_if_missing(conn, "invoices", "classified_by", "TEXT")?;
+    add_column_if_missing(conn, "invoices", "bounding_box", "TEXT")?;
+    add_column_if_missing(conn, "invoices", "page_number", "INTEGER")?;
+    add_column_if_missing(conn, "invoices", "warnings", "TEXT")?;
+    Ok(())
+}
+
+fn migrate_v3_processed_file_path(conn: &Connection) -> AppResult<()> {
+    add_column_if_missing(conn, "invoices", "processed_file_path", "TEXT")
+}
+
+fn migrate_v4_attachments(conn: &Connection) -> AppResult<()> {
+    create_attachments_table(conn)?;
+    add_column_if_missing(conn, "invoices", "attachment_hash", "TEXT")?;
+    create_attachment_index(conn)
+}
+
+fn migrate_v5_unique_code_number(conn: &Connection) -> AppResult<()> {
+    add_column_if_missing(conn, "invoices", "duplicate_of", "TEXT")?;
+    create_unique_code_number_index(conn)
+}
+
+/// v6：金额列由 REAL（元）改为 INTEGER（分）
+///
+/// SQLite 不支持修改列类型，需要重建发票表；依赖发票表的视图和索引一并重建。
+fn migrate_v6_money_in_fen(conn: &Connection) -> AppResult<()> {
+    conn.execute_batch(
+        "DROP VIEW IF EXISTS v_monthly_stats;
+        DROP VIEW IF EXISTS v_category_stats;
+        ALTER TABLE invoices RENAME TO invoices_v5;",
+    )?;
+    conn.execute(
+        "CREATE TABLE invoices (
+            id TEXT PRIMARY KEY,
+            invoice_type TEXT NOT NULL,
+            invoice_code TEXT,
+            invoice_number TEXT,
+            invoice_date TEXT,
+            amount_without_tax INTEGER,
+            tax_amount INTEGER,
+            total_amount INTEGER NOT NULL,
+            buyer_name TEXT,
+            buyer_tax_number TEXT,
+            seller_name TEXT,
+            seller_tax_number TEXT,
+            commodity_name TEXT,
+            commodity_detail TEXT,
+            check_code TEXT,
+            machine_code TEXT,
+            original_file_path TEXT,
+            file_type TEXT,
+            ocr_raw_response TEXT,
+            ocr_confidence REAL,
+            category TEXT,
+            remark TEXT,
+            is_verified INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            extra_data TEXT,
+            classified_by TEXT,
+            bounding_box TEXT,
+            page_number INTEGER,
+            warnings TEXT,
+            processed_file_path TEXT,
+            attachment_hash TEXT,
+            duplicate_of TEXT
+        )",
+        [],
+    )?;
+
+    let columns = "id, invoice_type, invoice_code, invoice_number, invoice_date,
+        buyer_name, buyer_tax_number, seller_name, seller_tax_number,
+        commodity_name, commodity_detail, check_code, machine_code,
+        original_file_path, file_type, ocr_raw_response, ocr_confidence,
+        category, remark, is_verified, created_at, updated_at,
+        extra_data, classified_by, bounding_box, page_number, warnings,
+        processed_file_path, attachment_hash, duplicate_of";
+    conn.execute(
+        &format!(
+            "INSERT INTO invoices ({columns}, amount_without_tax, tax_amount, total_amount)
+            SELECT {columns},
+                CAST(ROUND(amount_without_tax * 100) AS INTEGER),
+                CAST(ROUND(tax_amount * 100) AS INTEGER),
+                CAST(ROUND(total_amount * 100) AS INTEGER)
+            FROM invoices_v5"
+        ),
+        [],
+    )?;
+    conn.execute("DROP TABLE invoices_v5", [])?;
+
+    create_indexes(conn)?;
+    create_attachment_index(conn)?;
+    create_unique_code_number_index(conn)?;
+    create_money_views(conn)?;
+    Ok(())
+}
+
+/// v7：购买方核对结果，已有发票为空（未核对）
+fn migrate_v7_buyer_check(conn: &Connection) -> AppResult<()> {
+    add_column_if_missing(conn, "invoices", "buyer_check", "TEXT")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_invoices_buyer_check ON invoices(buyer_check)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v8：旧版本按字符串替换生成的日期（如 `2024-1-5`）补零，无法解析的日期清空并记入提示
+///
+/// 开票日期按字符串比较筛选，不补零会导致范围筛选出错。
+fn migrate_v8_normalize_invoice_dates(conn: &Connection) -> AppResult<()> {
+    let rows = {
+        let mut stmt = conn.prepare(
+            "SELECT id, invoice_date, warnings FROM invoices WHERE invoice_date IS NOT NULL",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    for (id, date, mut warnings) in rows {
+        let normalized = v8_normalize_date(&date);
+        if normalized.as_deref() == Some(date.as_str()) {
+            continue;
+        }
+        if normalized.is_none() {
+            warnings = Some(v8_append_invalid_date(warnings.as_deref(), &date));
+        }
+        conn.execute(
+            "UPDATE invoices SET invoice_date = ?2, warnings = ?3 WHERE id = ?1",
+            params![id, normalized, warnings],
+        )?;
+    }
+    Ok(())
+}
+
+/// v8 迁移时的日期格式。迁移只按当时的规则执行一次，不随 `parse_date` 后续的修改而变化
+const V8_DATE_FORMATS: [&str; 4] = ["%Y年%m月%d日", "%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"];
+
+/// v8 迁移时的日期解析规则（`parse_date` 当时的副本）
+fn v8_normalize_date(date: &str) -> Option<String> {
+    let s = date.trim();
+    let date: String = match s.find('日') {
+        Some(end) => s[..end + '日'.len_utf8()]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect(),
+        None => s
+            .split(|c: char| c.is_whitespace() || c == 'T')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    };
+
+    let parsed = if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) {
+        NaiveDate::from_ymd_opt(
+            date[..4].parse().ok()?,
+            date[4..6].parse().ok()?,
+            date[6..].parse().ok()?,
+        )
+    } else {
+        V8_DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(&date, format).ok())
+    };
+    parsed
+        .filter(|d| (1900..=9999).contains(&d.year()))
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+/// 按 v8 时的提示格式追加 `invalid_date`，已有提示原样保留
+fn v8_append_invalid_date(warnings: Option<&str>, value: &str) -> String {
+    let mut list: Vec<serde_json::Value> = warnings
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    list.push(serde_json::json!({ "kind": "invalid_date", "value": value }));
+    serde_json::Value::Array(list).to_string()
+}
+
+/// 创建发票表（v1 结构）
 fn create_invoices_table(conn: &Connection) -> AppResult<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS invoices (
@@ -46,6 +344,27 @@ fn create_invoices_table(conn: &Connection) -> AppResult<()> {
     Ok(())
 }
 
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> AppResult<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>("name"))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
 /// 创建配置表
 fn create_configs_table(conn: &Connection) -> AppResult<()> {
     conn.execute(
@@ -60,6 +379,21 @@ fn create_configs_table(conn: &Connection) -> AppResult<()> {
     Ok(())
 }
 
+/// 创建附件表
+fn create_attachments_table(conn: &Connection) -> AppResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            hash TEXT PRIMARY KEY,
+            size INTEGER NOT NULL,
+            mime_type TEXT NOT NULL,
+            file_name TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
 /// 创建索引
 fn create_indexes(conn: &Connection) -> AppResult<()> {
     // 发票日期索引
@@ -95,6 +429,50 @@ fn create_indexes(conn: &Connection) -> AppResult<()> {
     Ok(())
 }
 
+/// 发票代码 + 号码唯一（忽略空值和已标记的重复记录）
+///
+/// 旧数据中已存在的重复记录先标记为最早一条的副本，再建立索引。
+fn create_unique_code_number_index(conn: &Connection) -> AppResult<()> {
+    conn.execute(
+        "UPDATE invoices SET duplicate_of = (
+            SELECT first.id FROM invoices AS first
+            WHERE first.invoice_code = invoices.invoice_code
+              AND first.invoice_number = invoices.invoice_number
+              AND first.duplicate_of IS NULL
+            ORDER BY first.created_at, first.id
+            LIMIT 1
+        )
+        WHERE invoice_code IS NOT NULL
+          AND invoice_number IS NOT NULL
+          AND duplicate_of IS NULL
+          AND EXISTS (
+            SELECT 1 FROM invoices AS earlier
+            WHERE earlier.invoice_code = invoices.invoice_code
+              AND earlier.invoice_number = invoices.invoice_number
+              AND earlier.duplicate_of IS NULL
+              AND (earlier.created_at < invoices.created_at
+                   OR (earlier.created_at = invoices.created_at AND earlier.id < invoices.id))
+          )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_invoices_code_number
+         ON invoices(invoice_code, invoice_number)
+         WHERE invoice_code IS NOT NULL AND invoice_number IS NOT NULL AND duplicate_of IS NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 附件索引（清理未引用附件时使用）
+fn create_attachment_index(conn: &Connection) -> AppResult<()> {
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_invoices_attachment ON invoices(attachment_hash)",
+        [],
+    )?;
+    Ok(())
+}
+
 /// 创建统计视图（预留）
 fn create_views(conn: &Connection) -> AppResult<()> {
     // 月度统计视图
@@ -126,3 +504,141 @@ fn create_views(conn: &Connection) -> AppResult<()> {
 
     Ok(())
 }
+
+/// 创建统计视图（v6 起金额合计以分为单位）
+fn create_money_views(conn: &Connection) -> AppResult<()> {
+    // 月度统计视图
+    conn.execute(
+        "CREATE VIEW IF NOT EXISTS v_monthly_stats AS
+        SELECT
+            strftime('%Y-%m', invoice_date) as month,
+            invoice_type,
+            COUNT(*) as count,
+            SUM(total_amount) as total_amount_fen,
+            SUM(tax_amount) as total_tax_fen
+        FROM invoices
+        WHERE invoice_date IS NOT NULL
+        GROUP BY strftime('%Y-%m', invoice_date), invoice_type",
+        [],
+    )?;
+
+    // 分类统计视图
+    conn.execute(
+        "CREATE VIEW IF NOT EXISTS v_category_stats AS
+        SELECT
+            category,
+            COUNT(*) as count,
+            SUM(total_amount) as total_amount_fen
+        FROM invoices
+        GROUP BY category",
+        [],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::warning::InvoiceWarning;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get("name"))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_upgrade_from_v1() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate_to(&conn, 1).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        assert!(!columns(&conn, "invoices").contains(&"warnings".to_string()));
+
+        // v1 时期的数据，含两条代码 + 号码相同的记录，日期未补零或不存在
+        for (id, date, created_at) in [
+            ("a", "2024-1-5", "2024-01-01T00:00:00Z"),
+            ("b", "2023-2-30", "2024-01-02T00:00:00Z"),
+        ] {
+            conn.execute(
+                "INSERT INTO invoices (id, invoice_type, invoice_code, invoice_number,
+                    invoice_date, total_amount, created_at, updated_at)
+                 VALUES (?1, 'vat_invoice', '044001900111', '05968312', ?2, 1130.0, ?3, ?3)",
+                params![id, date, created_at],
+            )
+            .unwrap();
+        }
+
+        run_migrations(&conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        let invoice_columns = columns(&conn, "invoices");
+        for column in [
+            "extra_data",
+            "warnings",
+            "processed_file_path",
+            "attachment_hash",
+            "duplicate_of",
+            "buyer_check",
+        ] {
+            assert!(
+                invoice_columns.contains(&column.to_string()),
+                "missing {}",
+                column
+            );
+        }
+        assert!(!columns(&conn, "attachments").is_empty());
+
+        let (total, kind): (i64, String) = conn
+            .query_row(
+                "SELECT total_amount, typeof(total_amount) FROM invoices WHERE id = 'a'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((total, kind.as_str()), (113000, "integer"));
+        let monthly: Option<i64> = conn
+            .query_row(
+                "SELECT SUM(total_amount_fen) FROM v_monthly_stats",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(monthly, Some(113000));
+
+        let dates: Vec<(Option<String>, Option<String>)> = conn
+            .prepare("SELECT invoice_date, warnings FROM invoices ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(dates[0], (Some("2024-01-05".to_string()), None));
+        assert_eq!(dates[1].0, None);
+        assert_eq!(
+            InvoiceWarning::list_from_json(dates[1].1.as_deref()),
+            vec![InvoiceWarning::InvalidDate {
+                value: "2023-2-30".to_string()
+            }]
+        );
+
+        let duplicate_of: Option<String> = conn
+            .query_row(
+                "SELECT duplicate_of FROM invoices WHERE id = 'b'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(duplicate_of.as_deref(), Some("a"));
+
+        // 重复执行不会再次应用
+        run_migrations(&conn).unwrap();
+        let applied: u32 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied, latest_version());
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::company::BuyerCheck;
 use crate::models::invoice::InvoiceType;
+use crate::models::money::Money;
 
 /// 筛选条件
 #[derive(Debug, Clone, Default)]
@@ -8,10 +10,11 @@ pub struct InvoiceFilter {
     pub invoice_type: Option<InvoiceType>,
     pub date_from: Option<String>,
     pub date_to: Option<String>,
-    pub amount_min: Option<f64>,
-    pub amount_max: Option<f64>,
+    pub amount_min: Option<Money>,
+    pub amount_max: Option<Money>,
     pub keyword: Option<String>,
     pub category: Option<String>,
+    pub buyer_check: Option<BuyerCheck>,
 }
 
 /// 分页参数
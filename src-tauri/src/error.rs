@@ -12,6 +12,9 @@ pub enum AppError {
     #[error("OCR 错误: {0}")]
     Ocr(String),
 
+    #[error("OCR 鉴权失败: {0}")]
+    Auth(String),
+
     #[error("配置错误: {0}")]
     Config(String),
 
@@ -23,6 +26,9 @@ pub enum AppError {
 
     #[error("网络请求错误: {0}")]
     Request(String),
+
+    #[error("发票已存在（ID: {existing_id}）")]
+    Duplicate { existing_id: String },
 }
 
 impl From<reqwest::Error> for AppError {
@@ -59,10 +65,12 @@ impl AppError {
             AppError::Database(_) => "database",
             AppError::Io(_) => "io",
             AppError::Ocr(_) => "ocr",
+            AppError::Auth(_) => "auth",
             AppError::Config(_) => "config",
             AppError::FileProcess(_) => "file_process",
             AppError::Serialization(_) => "serialization",
             AppError::Request(_) => "request",
+            AppError::Duplicate { .. } => "duplicate",
         }
     }
 }
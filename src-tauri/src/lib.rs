@@ -3,12 +3,14 @@ pub mod db;
 pub mod error;
 pub mod models;
 pub mod services;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 use commands::{
     delete_config, delete_invoice, delete_invoices, export_all_invoices, export_invoices,
-    get_config, get_file_base64, get_invoice, get_invoices, get_supported_extensions,
-    recognize_and_save_invoice, recognize_invoice, recognize_invoices_batch, set_config,
-    test_ocr_connection, update_invoice, validate_file,
+    get_attachment, get_config, get_file_base64, get_invoice, get_invoices,
+    get_supported_extensions, recheck_buyers, recognize_and_save_invoice, recognize_invoice,
+    recognize_invoices_batch, set_config, test_ocr_connection, update_invoice, validate_file,
 };
 
 /// 应用初始化
@@ -43,6 +45,7 @@ pub fn run() {
             recognize_invoice,
             recognize_and_save_invoice,
             recognize_invoices_batch,
+            recheck_buyers,
             test_ocr_connection,
             // 导出相关
             export_invoices,
@@ -51,6 +54,7 @@ pub fn run() {
             validate_file,
             get_file_base64,
             get_supported_extensions,
+            get_attachment,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
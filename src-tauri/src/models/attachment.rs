@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// 归档的原始文件，按内容 SHA-256 存放
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    /// 文件内容的 SHA-256（十六进制）
+    pub hash: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// MIME 类型
+    pub mime_type: String,
+    /// 首次归档时的文件名
+    pub file_name: Option<String>,
+    /// 创建时间 ISO8601
+    pub created_at: String,
+}
+
+/// 返回给前端的附件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentContent {
+    #[serde(flatten)]
+    pub attachment: Attachment,
+    /// 文件内容 base64
+    pub data: String,
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// 本公司主体，报销的发票购买方须为其中之一
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompanyEntity {
+    pub name: String,
+    pub tax_number: String,
+}
+
+/// 购买方核对结果，存入 `invoices.buyer_check`；未配置公司主体时不核对
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuyerCheck {
+    /// 购买方名称和税号与某一主体一致
+    Matched,
+    /// 购买方不是本公司
+    Mismatch,
+    /// 票面没有购买方信息（如火车票、出租车票）
+    Missing,
+}
+
+impl BuyerCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BuyerCheck::Matched => "matched",
+            BuyerCheck::Mismatch => "mismatch",
+            BuyerCheck::Missing => "missing",
+        }
+    }
+
+    /// 从数据库或筛选参数解析，无法识别时返回 `None`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "matched" => Some(BuyerCheck::Matched),
+            "mismatch" => Some(BuyerCheck::Mismatch),
+            "missing" => Some(BuyerCheck::Missing),
+            _ => None,
+        }
+    }
+}
+
+/// 购买方不是本公司时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuyerPolicy {
+    /// 照常保存，并在发票上记录提示
+    #[default]
+    Warn,
+    /// 不保存
+    Reject,
+}
+
+impl BuyerPolicy {
+    /// 从配置值解析，无法识别时返回 `None`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "warn" => Some(BuyerPolicy::Warn),
+            "reject" => Some(BuyerPolicy::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// 因购买方不是本公司而未保存的发票
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedBuyer {
+    pub invoice_number: Option<String>,
+    pub buyer_name: Option<String>,
+    pub buyer_tax_number: Option<String>,
+}
@@ -41,10 +41,14 @@ impl Config {
 
 /// 预定义的配置键常量
 pub mod config_keys {
+    /// OCR 服务商（默认 baidu）
+    pub const OCR_PROVIDER: &str = "ocr_provider";
     /// 百度 OCR API Key
     pub const BAIDU_OCR_API_KEY: &str = "baidu_ocr_api_key";
     /// 百度 OCR Secret Key
     pub const BAIDU_OCR_SECRET_KEY: &str = "baidu_ocr_secret_key";
+    /// 百度 OCR 服务地址（留空使用官方地址，可指向本地 mock 服务）
+    pub const BAIDU_OCR_BASE_URL: &str = "baidu_ocr_base_url";
     /// 百度 OCR Access Token（缓存）
     pub const BAIDU_OCR_ACCESS_TOKEN: &str = "baidu_ocr_access_token";
     /// Token 过期时间
@@ -53,4 +57,10 @@ pub mod config_keys {
     pub const EXPORT_DEFAULT_PATH: &str = "export_default_path";
     /// 导出模板配置
     pub const EXPORT_TEMPLATE: &str = "export_template";
+    /// 重复发票处理方式（reject / warn / merge，默认 warn）
+    pub const DUPLICATE_POLICY: &str = "duplicate_policy";
+    /// 本公司主体列表（JSON 数组，元素为 `{"name", "tax_number"}`）
+    pub const COMPANY_ENTITIES: &str = "company_entities";
+    /// 购买方不是本公司时的处理方式（warn / reject，默认 warn）
+    pub const BUYER_POLICY: &str = "buyer_policy";
 }
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// 识别到重复发票时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// 不保存新识别的发票
+    Reject,
+    /// 照常保存，并在发票上记录提示
+    #[default]
+    Warn,
+    /// 用新识别结果补全已有发票的空白字段
+    Merge,
+}
+
+impl DuplicatePolicy {
+    /// 从配置值解析，无法识别时返回 `None`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "reject" => Some(DuplicatePolicy::Reject),
+            "warn" => Some(DuplicatePolicy::Warn),
+            "merge" => Some(DuplicatePolicy::Merge),
+            _ => None,
+        }
+    }
+}
+
+/// 判定为重复的依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    /// 发票代码 + 发票号码相同
+    CodeNumber,
+    /// 同一文件（同一页）已导入
+    FileHash,
+    /// 销售方、开票日期、价税合计均相同
+    Fuzzy,
+}
+
+/// 一张重复发票的检测结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateMatch {
+    /// 已有发票 ID
+    pub existing_id: String,
+    pub reason: DuplicateReason,
+    /// 实际采取的处理方式
+    pub policy: DuplicatePolicy,
+    /// 新识别发票的号码
+    pub invoice_number: Option<String>,
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use super::company::BuyerCheck;
+use super::money::Money;
+
 /// 发票类型枚举
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -74,6 +77,17 @@ impl InvoiceType {
             InvoiceType::Other => "其他",
         }
     }
+
+    /// 是否为增值税发票
+    pub fn is_vat(&self) -> bool {
+        matches!(
+            self,
+            InvoiceType::VatInvoice
+                | InvoiceType::VatCommonInvoice
+                | InvoiceType::VatElectronicInvoice
+                | InvoiceType::VatRollInvoice
+        )
+    }
 }
 
 /// 发票数据模型
@@ -90,11 +104,11 @@ pub struct Invoice {
     /// 开票日期 (YYYY-MM-DD)
     pub invoice_date: Option<String>,
     /// 不含税金额
-    pub amount_without_tax: Option<f64>,
+    pub amount_without_tax: Option<Money>,
     /// 税额
-    pub tax_amount: Option<f64>,
+    pub tax_amount: Option<Money>,
     /// 价税合计（主要金额字段）
-    pub total_amount: f64,
+    pub total_amount: Money,
     /// 购买方名称
     pub buyer_name: Option<String>,
     /// 购买方税号
@@ -107,18 +121,45 @@ pub struct Invoice {
     pub commodity_name: Option<String>,
     /// 商品明细 JSON
     pub commodity_detail: Option<String>,
+    /// 票种扩展信息 JSON（见 `InvoiceExtra`）
+    #[serde(default)]
+    pub extra_data: Option<String>,
     /// 校验码
     pub check_code: Option<String>,
     /// 机器编号
     pub machine_code: Option<String>,
     /// 原始文件路径
     pub original_file_path: Option<String>,
-    /// 文件类型 (image/pdf)
+    /// 预处理后的图片路径（启用预处理时）
+    #[serde(default)]
+    pub processed_file_path: Option<String>,
+    /// 归档原始文件的 SHA-256（见 `Attachment`）
+    #[serde(default)]
+    pub attachment_hash: Option<String>,
+    /// 所在 PDF 页码（从 1 开始）
+    #[serde(default)]
+    pub page_number: Option<u32>,
+    /// 票据在原始文件中的位置 JSON（一图多票时）
+    #[serde(default)]
+    pub bounding_box: Option<String>,
+    /// 文件类型 (image/pdf/ofd)
     pub file_type: Option<String>,
     /// OCR 原始响应 JSON
     pub ocr_raw_response: Option<String>,
     /// OCR 置信度
     pub ocr_confidence: Option<f64>,
+    /// 判定票种的分类器（user / keyword / baidu_multiple_invoice 等）
+    #[serde(default)]
+    pub classified_by: Option<String>,
+    /// 重复导入时指向的已有发票 ID（不参与代码 + 号码唯一约束）
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    /// 购买方是否为本公司，未配置公司主体时为空
+    #[serde(default)]
+    pub buyer_check: Option<BuyerCheck>,
+    /// 识别提示 JSON（见 `InvoiceWarning`）
+    #[serde(default)]
+    pub warnings: Option<String>,
     /// 分类标签
     pub category: Option<String>,
     /// 备注
@@ -133,7 +174,7 @@ pub struct Invoice {
 
 impl Invoice {
     /// 创建新发票
-    pub fn new(invoice_type: InvoiceType, total_amount: f64) -> Self {
+    pub fn new(invoice_type: InvoiceType, total_amount: Money) -> Self {
         let now = chrono::Utc::now().to_rfc3339();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
@@ -150,12 +191,21 @@ impl Invoice {
             seller_tax_number: None,
             commodity_name: None,
             commodity_detail: None,
+            extra_data: None,
             check_code: None,
             machine_code: None,
             original_file_path: None,
+            processed_file_path: None,
+            attachment_hash: None,
+            page_number: None,
+            bounding_box: None,
             file_type: None,
             ocr_raw_response: None,
             ocr_confidence: None,
+            classified_by: None,
+            duplicate_of: None,
+            buyer_check: None,
+            warnings: None,
             category: None,
             remark: None,
             is_verified: false,
@@ -163,4 +213,129 @@ impl Invoice {
             updated_at: now,
         }
     }
+
+    /// 用另一张发票的识别结果补全本发票的空白字段
+    pub fn fill_missing_from(&mut self, other: &Invoice) {
+        fn fill<T: Clone>(target: &mut Option<T>, source: &Option<T>) {
+            if target.is_none() {
+                target.clone_from(source);
+            }
+        }
+
+        fill(&mut self.invoice_code, &other.invoice_code);
+        fill(&mut self.invoice_number, &other.invoice_number);
+        fill(&mut self.invoice_date, &other.invoice_date);
+        fill(&mut self.amount_without_tax, &other.amount_without_tax);
+        fill(&mut self.tax_amount, &other.tax_amount);
+        fill(&mut self.buyer_name, &other.buyer_name);
+        fill(&mut self.buyer_tax_number, &other.buyer_tax_number);
+        fill(&mut self.seller_name, &other.seller_name);
+        fill(&mut self.seller_tax_number, &other.seller_tax_number);
+        fill(&mut self.commodity_name, &other.commodity_name);
+        fill(&mut self.commodity_detail, &other.commodity_detail);
+        fill(&mut self.extra_data, &other.extra_data);
+        fill(&mut self.check_code, &other.check_code);
+        fill(&mut self.machine_code, &other.machine_code);
+    }
+}
+
+/// 票种扩展信息，序列化后存入 `Invoice.extra_data`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InvoiceExtra {
+    /// 火车票
+    TrainTicket(TrainTicketExtra),
+    /// 出租车票
+    TaxiTicket(TaxiTicketExtra),
+    /// 机票行程单
+    FlightItinerary(FlightItineraryExtra),
+}
+
+impl InvoiceExtra {
+    /// 转换为 JSON 字符串
+    pub fn to_json(&self) -> Option<String> {
+        serde_json::to_string(self).ok()
+    }
+}
+
+/// 火车票扩展信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrainTicketExtra {
+    /// 出发站
+    pub departure_station: Option<String>,
+    /// 到达站
+    pub arrival_station: Option<String>,
+    /// 车次
+    pub train_number: Option<String>,
+    /// 座位类型
+    pub seat_class: Option<String>,
+    /// 座位号
+    pub seat_number: Option<String>,
+    /// 乘车人姓名
+    pub passenger_name: Option<String>,
+    /// 乘车日期 (YYYY-MM-DD)
+    pub departure_date: Option<String>,
+    /// 发车时间
+    pub departure_time: Option<String>,
+    /// 票价
+    pub ticket_price: Option<Money>,
+}
+
+/// 出租车票扩展信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaxiTicketExtra {
+    /// 上车时间
+    pub pickup_time: Option<String>,
+    /// 下车时间
+    pub dropoff_time: Option<String>,
+    /// 里程（公里）
+    pub mileage: Option<f64>,
+    /// 单价（元/公里）
+    pub unit_price: Option<Money>,
+    /// 车费
+    pub fare: Option<Money>,
+    /// 燃油附加费
+    pub fuel_surcharge: Option<Money>,
+    /// 车牌号
+    pub taxi_number: Option<String>,
+    /// 所在城市
+    pub city: Option<String>,
+}
+
+/// 机票行程单扩展信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlightItineraryExtra {
+    /// 旅客姓名
+    pub passenger_name: Option<String>,
+    /// 有效身份证件号码
+    pub id_number: Option<String>,
+    /// 航段
+    pub legs: Vec<FlightLeg>,
+    /// 票价
+    pub fare: Option<Money>,
+    /// 民航发展基金
+    pub development_fund: Option<Money>,
+    /// 燃油附加费
+    pub fuel_surcharge: Option<Money>,
+    /// 其他税费
+    pub other_tax: Option<Money>,
+    /// 保险费
+    pub insurance: Option<Money>,
+    /// 可抵扣增值税：(票价 + 燃油附加费) ÷ (1 + 9%) × 9%
+    pub deductible_tax: Option<Money>,
+}
+
+/// 航段
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlightLeg {
+    /// 出发地
+    pub from: Option<String>,
+    /// 目的地
+    pub to: Option<String>,
+    /// 航班号
+    pub flight_number: Option<String>,
+    /// 乘机日期 (YYYY-MM-DD)
+    pub date: Option<String>,
+    /// 起飞时间
+    pub time: Option<String>,
 }
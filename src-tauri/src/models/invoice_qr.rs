@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use super::money::Money;
+use super::ocr_response::parse_date;
+
+/// 发票二维码内容
+///
+/// 格式为逗号分隔：`版本,票种代码,发票代码,发票号码,不含税金额,开票日期(YYYYMMDD),校验码,随机码`，
+/// 数电票的发票代码与校验码为空。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceQrCode {
+    /// 票种代码，如 01 专票、04 普票、10 电子普票、31/32 数电票
+    pub type_code: String,
+    pub invoice_code: Option<String>,
+    pub invoice_number: String,
+    pub amount_without_tax: Option<Money>,
+    /// 开票日期 YYYY-MM-DD
+    pub invoice_date: Option<String>,
+    pub check_code: Option<String>,
+}
+
+impl InvoiceQrCode {
+    /// 解析二维码文本，不是发票二维码时返回 `None`
+    pub fn parse(content: &str) -> Option<Self> {
+        let fields: Vec<&str> = content.trim().split(',').map(str::trim).collect();
+        if fields.len() < 7 || fields[0] != "01" {
+            return None;
+        }
+
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        let invoice_number = non_empty(fields[3])?;
+        if !invoice_number.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        // 二维码中为 YYYYMMDD，不存在的日期视为缺失
+        let invoice_date = parse_date(Some(fields[5]));
+
+        Some(Self {
+            type_code: fields[1].to_string(),
+            invoice_code: non_empty(fields[2]),
+            invoice_number,
+            amount_without_tax: Money::parse(fields[4]),
+            invoice_date,
+            check_code: non_empty(fields[6]),
+        })
+    }
+}
@@ -1,6 +1,18 @@
+pub mod attachment;
+pub mod company;
 pub mod config;
+pub mod duplicate;
 pub mod invoice;
+pub mod invoice_qr;
+pub mod money;
 pub mod ocr_response;
+pub mod warning;
 
+pub use attachment::{Attachment, AttachmentContent};
+pub use company::{BuyerCheck, BuyerPolicy, CompanyEntity, RejectedBuyer};
 pub use config::Config;
-pub use invoice::{Invoice, InvoiceType};
+pub use duplicate::{DuplicateMatch, DuplicatePolicy, DuplicateReason};
+pub use invoice::{Invoice, InvoiceExtra, InvoiceType};
+pub use invoice_qr::InvoiceQrCode;
+pub use money::Money;
+pub use warning::InvoiceWarning;
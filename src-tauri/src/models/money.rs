@@ -0,0 +1,191 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Sub};
+
+/// 金额，以分为单位的整数保存，避免浮点累加误差
+///
+/// 数据库中存为 INTEGER（分）；JSON 中仍是以元为单位的数字，前端无需改动。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_fen(fen: i64) -> Self {
+        Money(fen)
+    }
+
+    pub const fn fen(self) -> i64 {
+        self.0
+    }
+
+    /// 由以元为单位的浮点数转换，四舍五入到分
+    pub fn from_yuan(yuan: f64) -> Self {
+        Money((yuan * 100.0).round() as i64)
+    }
+
+    pub fn to_yuan(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// 解析以元为单位的金额文本（如 `¥1,130.00元`），按十进制逐位解析，不经过浮点
+    ///
+    /// 超过两位的小数四舍五入到分。
+    pub fn parse(s: &str) -> Option<Self> {
+        let cleaned: String = s
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+            .collect();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.as_str()),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (int_part.is_empty() && frac_part.is_empty())
+            || !all_digits(int_part)
+            || !all_digits(frac_part)
+        {
+            return None;
+        }
+
+        let yuan: i64 = if int_part.is_empty() {
+            0
+        } else {
+            int_part.parse().ok()?
+        };
+        let mut frac = frac_part.bytes().map(|b| (b - b'0') as i64);
+        let jiao = frac.next().unwrap_or(0);
+        let fen = frac.next().unwrap_or(0);
+        let round_up = frac.next().is_some_and(|d| d >= 5) as i64;
+
+        let value = yuan.checked_mul(100)? + jiao * 10 + fen + round_up;
+        Some(Money(if negative { -value } else { value }))
+    }
+
+    pub fn abs(self) -> Self {
+        Money(self.0.abs())
+    }
+
+    /// 含税金额中包含的税额：金额 ÷ (1 + 税率) × 税率，`rate_percent` 为百分比，四舍五入到分
+    pub fn included_tax(self, rate_percent: i64) -> Self {
+        let numerator = self.0 * rate_percent;
+        let denominator = 100 + rate_percent;
+        Money((2 * numerator + numerator.signum() * denominator) / (2 * denominator))
+    }
+}
+
+impl fmt::Display for Money {
+    /// 以元为单位、保留两位小数
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let fen = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, fen / 100, fen % 100)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_yuan())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    /// 接受以元为单位的数字或字符串
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Yuan {
+            Number(f64),
+            Text(String),
+        }
+
+        match Yuan::deserialize(deserializer)? {
+            Yuan::Number(yuan) => Ok(Money::from_yuan(yuan)),
+            Yuan::Text(text) => Money::parse(&text)
+                .ok_or_else(|| serde::de::Error::custom(format!("无效的金额: {}", text))),
+        }
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0))
+    }
+}
+
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(fen) => Ok(Money(fen)),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        assert_eq!(Money::parse("¥1,130.00元"), Some(Money::from_fen(113000)));
+        assert_eq!(Money::parse("553.0"), Some(Money::from_fen(55300)));
+        assert_eq!(Money::parse("0.1"), Some(Money::from_fen(10)));
+        assert_eq!(Money::parse("-12.345"), Some(Money::from_fen(-1235)));
+        assert_eq!(Money::parse("1.2.3"), None);
+        assert_eq!(Money::parse("元"), None);
+
+        // 0.1 + 0.2 在浮点下不等于 0.3
+        let sum: Money = ["0.1", "0.2"].iter().filter_map(|s| Money::parse(s)).sum();
+        assert_eq!(sum, Money::from_fen(30));
+        assert_eq!(Money::from_fen(-5).to_string(), "-0.05");
+        assert_eq!(
+            serde_json::to_string(&Money::from_fen(113050)).unwrap(),
+            "1130.5"
+        );
+    }
+
+    #[test]
+    fn test_included_tax() {
+        // 1308.00 / 1.09 * 0.09 = 108.00
+        assert_eq!(
+            Money::from_fen(130800).included_tax(9),
+            Money::from_fen(10800)
+        );
+        // 1000.00 / 1.09 * 0.09 = 82.568… 四舍五入
+        assert_eq!(
+            Money::from_fen(100000).included_tax(9),
+            Money::from_fen(8257)
+        );
+        assert_eq!(
+            Money::from_fen(-100000).included_tax(9),
+            Money::from_fen(-8257)
+        );
+        assert_eq!(Money::ZERO.included_tax(9), Money::ZERO);
+    }
+}
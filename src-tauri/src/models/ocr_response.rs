@@ -1,4 +1,11 @@
-use crate::models::invoice::{Invoice, InvoiceType};
+use crate::models::invoice::{
+    FlightItineraryExtra, FlightLeg, Invoice, InvoiceExtra, InvoiceType, TaxiTicketExtra,
+    TrainTicketExtra,
+};
+use crate::models::invoice_qr::InvoiceQrCode;
+use crate::models::money::Money;
+use crate::models::warning::InvoiceWarning;
+use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 
 /// 百度 OCR Token 响应
@@ -40,6 +47,8 @@ pub struct VatInvoiceWordsResult {
     pub commodity_name: Option<Vec<CommodityItem>>,
     #[serde(rename = "CommodityAmount")]
     pub commodity_amount: Option<Vec<CommodityItem>>,
+    #[serde(rename = "CommodityTaxRate")]
+    pub commodity_tax_rate: Option<Vec<CommodityItem>>,
     #[serde(rename = "TotalAmount")]
     pub total_amount: Option<String>,
     #[serde(rename = "TotalTax")]
@@ -77,27 +86,46 @@ pub struct CommodityItem {
     pub row: Option<String>,
 }
 
+/// OCR 字段值：单个文本，或按行拆分的列表（多航段等场景）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OcrWords {
+    Text(String),
+    Rows(Vec<CommodityItem>),
+}
+
+impl OcrWords {
+    /// 展开为文本列表
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            OcrWords::Text(text) => vec![text.clone()],
+            OcrWords::Rows(rows) => rows.iter().map(|item| item.word.clone()).collect(),
+        }
+    }
+}
+
 impl VatInvoiceResponse {
-    /// 转换为 Invoice 模型
+    /// 转换为 Invoice 模型，有二维码时以二维码中的字段为准
     pub fn to_invoice(
         &self,
         file_path: Option<&str>,
         file_type: Option<&str>,
         raw_json: &str,
+        qr_code: Option<&InvoiceQrCode>,
     ) -> Invoice {
         let wr = &self.words_result;
         let invoice_type = Self::parse_invoice_type(wr.invoice_type.as_deref());
-        let total = Self::parse_amount(wr.amount_in_figures.as_deref())
-            .or_else(|| Self::parse_amount(wr.total_amount.as_deref()))
-            .unwrap_or(0.0);
+        let total = parse_money(wr.amount_in_figures.as_deref())
+            .or_else(|| parse_money(wr.total_amount.as_deref()))
+            .unwrap_or(Money::ZERO);
 
         let mut invoice = Invoice::new(invoice_type, total);
 
         invoice.invoice_code = wr.invoice_code.clone();
         invoice.invoice_number = wr.invoice_num.clone();
-        invoice.invoice_date = Self::parse_date(wr.invoice_date.as_deref());
-        invoice.amount_without_tax = Self::parse_amount(wr.total_amount.as_deref());
-        invoice.tax_amount = Self::parse_amount(wr.total_tax.as_deref());
+        invoice.invoice_date = parse_date(wr.invoice_date.as_deref());
+        invoice.amount_without_tax = parse_money(wr.total_amount.as_deref());
+        invoice.tax_amount = parse_money(wr.total_tax.as_deref());
         invoice.buyer_name = wr.purchaser_name.clone();
         invoice.buyer_tax_number = wr.purchaser_register_num.clone();
         invoice.seller_name = wr.seller_name.clone();
@@ -111,9 +139,80 @@ impl VatInvoiceResponse {
         invoice.ocr_raw_response = Some(raw_json.to_string());
         invoice.remark = wr.remarks.clone();
 
+        if let Some(qr_code) = qr_code {
+            let warnings = Self::apply_qr_code(&mut invoice, qr_code);
+            invoice.warnings = InvoiceWarning::list_to_json(&warnings);
+        }
+        // 二维码中的日期可以补上无法识别的票面日期
+        if invoice.invoice_date.is_none() {
+            if let Some(warning) = invalid_date(wr.invoice_date.as_deref()) {
+                InvoiceWarning::append_to(&mut invoice.warnings, warning);
+            }
+        }
+
         invoice
     }
 
+    /// 用二维码中的值覆盖 OCR 结果，返回两者不一致的字段
+    fn apply_qr_code(invoice: &mut Invoice, qr_code: &InvoiceQrCode) -> Vec<InvoiceWarning> {
+        let mut warnings = Vec::new();
+        let mut merge = |field: &str, target: &mut Option<String>, qr_value: Option<&String>| {
+            let Some(qr_value) = qr_value else {
+                return;
+            };
+            let ocr_value = target
+                .as_deref()
+                .map(|value| value.split_whitespace().collect::<String>())
+                .filter(|value| !value.is_empty());
+            if let Some(ocr_value) = ocr_value {
+                if ocr_value != *qr_value {
+                    warnings.push(InvoiceWarning::QrMismatch {
+                        field: field.to_string(),
+                        ocr_value,
+                        qr_value: qr_value.clone(),
+                    });
+                }
+            }
+            *target = Some(qr_value.clone());
+        };
+
+        merge(
+            "invoice_code",
+            &mut invoice.invoice_code,
+            qr_code.invoice_code.as_ref(),
+        );
+        merge(
+            "invoice_number",
+            &mut invoice.invoice_number,
+            Some(&qr_code.invoice_number),
+        );
+        merge(
+            "invoice_date",
+            &mut invoice.invoice_date,
+            qr_code.invoice_date.as_ref(),
+        );
+        merge(
+            "check_code",
+            &mut invoice.check_code,
+            qr_code.check_code.as_ref(),
+        );
+
+        if let Some(qr_amount) = qr_code.amount_without_tax {
+            if let Some(ocr_amount) = invoice.amount_without_tax {
+                if ocr_amount != qr_amount {
+                    warnings.push(InvoiceWarning::QrMismatch {
+                        field: "amount_without_tax".to_string(),
+                        ocr_value: ocr_amount.to_string(),
+                        qr_value: qr_amount.to_string(),
+                    });
+                }
+            }
+            invoice.amount_without_tax = Some(qr_amount);
+        }
+
+        warnings
+    }
+
     fn parse_invoice_type(type_str: Option<&str>) -> InvoiceType {
         match type_str {
             Some(s) if s.contains("专用") => InvoiceType::VatInvoice,
@@ -124,26 +223,6 @@ impl VatInvoiceResponse {
         }
     }
 
-    fn parse_amount(s: Option<&str>) -> Option<f64> {
-        s.and_then(|v| {
-            let cleaned: String = v
-                .chars()
-                .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
-                .collect();
-            cleaned.parse().ok()
-        })
-    }
-
-    fn parse_date(date_str: Option<&str>) -> Option<String> {
-        date_str.map(|s| {
-            s.replace("年", "-")
-                .replace("月", "-")
-                .replace("日", "")
-                .trim()
-                .to_string()
-        })
-    }
-
     fn join_commodity_names(items: Option<&Vec<CommodityItem>>) -> Option<String> {
         items.map(|list| {
             list.iter()
@@ -157,3 +236,1040 @@ impl VatInvoiceResponse {
         items.and_then(|list| serde_json::to_string(list).ok())
     }
 }
+
+/// 火车票 OCR 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainTicketResponse {
+    pub words_result: TrainTicketWordsResult,
+    pub words_result_num: Option<i32>,
+    pub log_id: Option<i64>,
+}
+
+/// 火车票 OCR 字段
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TrainTicketWordsResult {
+    /// 车票号
+    pub ticket_num: Option<String>,
+    pub starting_station: Option<String>,
+    pub destination_station: Option<String>,
+    pub train_num: Option<String>,
+    /// 乘车日期，如 2024年01月15日
+    pub date: Option<String>,
+    /// 发车时间，如 14:08
+    pub time: Option<String>,
+    /// 票价，如 ￥553.0元
+    pub ticket_rates: Option<String>,
+    pub seat_category: Option<String>,
+    pub seat_num: Option<String>,
+    /// 乘车人姓名
+    pub name: Option<String>,
+    #[serde(rename = "ID_num")]
+    pub id_num: Option<String>,
+    pub serial_number: Option<String>,
+    pub sales_station: Option<String>,
+}
+
+impl TrainTicketResponse {
+    /// 转换为 Invoice 模型
+    pub fn to_invoice(
+        &self,
+        file_path: Option<&str>,
+        file_type: Option<&str>,
+        raw_json: &str,
+    ) -> Invoice {
+        let wr = &self.words_result;
+        let price = parse_money(wr.ticket_rates.as_deref());
+        let departure_date = parse_date(wr.date.as_deref());
+
+        let total = price.unwrap_or(Money::ZERO);
+
+        let mut invoice = Invoice::new(InvoiceType::TrainTicket, total);
+
+        invoice.invoice_number = wr.ticket_num.clone();
+        invoice.invoice_date = departure_date.clone();
+        if let Some(warning) = invalid_date(wr.date.as_deref()) {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
+        invoice.commodity_name = Self::route_summary(wr);
+        invoice.extra_data = InvoiceExtra::TrainTicket(TrainTicketExtra {
+            departure_station: wr.starting_station.clone(),
+            arrival_station: wr.destination_station.clone(),
+            train_number: wr.train_num.clone(),
+            seat_class: wr.seat_category.clone(),
+            seat_number: wr.seat_num.clone(),
+            passenger_name: wr.name.clone(),
+            departure_date,
+            departure_time: wr.time.clone(),
+            ticket_price: price,
+        })
+        .to_json();
+        invoice.original_file_path = file_path.map(String::from);
+        invoice.file_type = file_type.map(String::from);
+        invoice.ocr_raw_response = Some(raw_json.to_string());
+
+        invoice
+    }
+
+    /// 生成行程摘要，如 "G101 北京南-上海虹桥"
+    fn route_summary(wr: &TrainTicketWordsResult) -> Option<String> {
+        let route = match (&wr.starting_station, &wr.destination_station) {
+            (Some(from), Some(to)) => Some(format!("{}-{}", from, to)),
+            (Some(station), None) | (None, Some(station)) => Some(station.clone()),
+            (None, None) => None,
+        };
+        match (&wr.train_num, route) {
+            (Some(train), Some(route)) => Some(format!("{} {}", train, route)),
+            (Some(train), None) => Some(train.clone()),
+            (None, route) => route,
+        }
+    }
+}
+
+/// 出租车票 OCR 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxiReceiptResponse {
+    pub words_result: TaxiReceiptWordsResult,
+    pub words_result_num: Option<i32>,
+    pub log_id: Option<i64>,
+}
+
+/// 出租车票 OCR 字段
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TaxiReceiptWordsResult {
+    #[serde(rename = "InvoiceCode")]
+    pub invoice_code: Option<String>,
+    #[serde(rename = "InvoiceNum")]
+    pub invoice_num: Option<String>,
+    #[serde(rename = "TaxiNum")]
+    pub taxi_num: Option<String>,
+    #[serde(rename = "Date")]
+    pub date: Option<String>,
+    /// 乘车时段，如 18:00-18:14
+    #[serde(rename = "Time")]
+    pub time: Option<String>,
+    #[serde(rename = "PickupTime")]
+    pub pickup_time: Option<String>,
+    #[serde(rename = "DropoffTime")]
+    pub dropoff_time: Option<String>,
+    #[serde(rename = "PricePerkm")]
+    pub price_per_km: Option<String>,
+    /// 里程，如 4.3公里
+    #[serde(rename = "Distance")]
+    pub distance: Option<String>,
+    #[serde(rename = "Fare")]
+    pub fare: Option<String>,
+    #[serde(rename = "FuelOilSurcharge")]
+    pub fuel_oil_surcharge: Option<String>,
+    #[serde(rename = "CallServiceSurcharge")]
+    pub call_service_surcharge: Option<String>,
+    #[serde(rename = "TotalFare")]
+    pub total_fare: Option<String>,
+    #[serde(rename = "Province")]
+    pub province: Option<String>,
+    #[serde(rename = "City")]
+    pub city: Option<String>,
+}
+
+impl TaxiReceiptResponse {
+    /// 转换为 Invoice 模型
+    pub fn to_invoice(
+        &self,
+        file_path: Option<&str>,
+        file_type: Option<&str>,
+        raw_json: &str,
+    ) -> Invoice {
+        let wr = &self.words_result;
+        let fare = parse_money(wr.fare.as_deref());
+        let total = parse_money(wr.total_fare.as_deref())
+            .or(fare)
+            .unwrap_or(Money::ZERO);
+        let (pickup_time, dropoff_time) = Self::split_time_range(wr);
+        let mileage = parse_amount(wr.distance.as_deref());
+        let city = wr.city.clone().or_else(|| wr.province.clone());
+
+        let mut invoice = Invoice::new(InvoiceType::TaxiTicket, total);
+
+        invoice.invoice_code = wr.invoice_code.clone();
+        invoice.invoice_number = wr.invoice_num.clone();
+        invoice.invoice_date = parse_date(wr.date.as_deref());
+        if let Some(warning) = invalid_date(wr.date.as_deref()) {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
+        invoice.commodity_name = Some(match (&city, mileage) {
+            (Some(city), Some(km)) => format!("出租车费 {} {}公里", city, km),
+            (Some(city), None) => format!("出租车费 {}", city),
+            (None, Some(km)) => format!("出租车费 {}公里", km),
+            (None, None) => "出租车费".to_string(),
+        });
+        invoice.extra_data = InvoiceExtra::TaxiTicket(TaxiTicketExtra {
+            pickup_time,
+            dropoff_time,
+            mileage,
+            unit_price: parse_money(wr.price_per_km.as_deref()),
+            fare,
+            fuel_surcharge: parse_money(wr.fuel_oil_surcharge.as_deref()),
+            taxi_number: wr.taxi_num.clone(),
+            city,
+        })
+        .to_json();
+        invoice.original_file_path = file_path.map(String::from);
+        invoice.file_type = file_type.map(String::from);
+        invoice.ocr_raw_response = Some(raw_json.to_string());
+
+        invoice
+    }
+
+    /// 拆分上下车时间，优先使用独立字段，否则解析 "18:00-18:14"
+    fn split_time_range(wr: &TaxiReceiptWordsResult) -> (Option<String>, Option<String>) {
+        if wr.pickup_time.is_some() || wr.dropoff_time.is_some() {
+            return (wr.pickup_time.clone(), wr.dropoff_time.clone());
+        }
+        match wr.time.as_deref().and_then(|t| t.split_once('-')) {
+            Some((start, end)) => (Some(start.trim().to_string()), Some(end.trim().to_string())),
+            None => (wr.time.clone(), None),
+        }
+    }
+}
+
+/// 机票行程单 OCR 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirTicketResponse {
+    pub words_result: AirTicketWordsResult,
+    pub words_result_num: Option<i32>,
+    pub log_id: Option<i64>,
+}
+
+/// 机票行程单 OCR 字段
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AirTicketWordsResult {
+    /// 旅客姓名
+    pub name: Option<String>,
+    pub id_num: Option<String>,
+    pub starting_station: Option<OcrWords>,
+    pub destination_station: Option<OcrWords>,
+    pub flight: Option<OcrWords>,
+    pub date: Option<OcrWords>,
+    pub time: Option<OcrWords>,
+    pub carrier: Option<OcrWords>,
+    /// 电子客票号码
+    pub ticket_number: Option<String>,
+    /// 票价
+    pub fare: Option<String>,
+    /// 民航发展基金
+    pub dev_fund: Option<String>,
+    pub fuel_surcharge: Option<String>,
+    pub other_tax: Option<String>,
+    pub insurance: Option<String>,
+    /// 合计
+    pub ticket_rates: Option<String>,
+    /// 填开单位
+    pub issued_by: Option<String>,
+    /// 填开日期
+    pub issued_date: Option<String>,
+    pub serial_number: Option<String>,
+    /// 验证码
+    pub ck: Option<String>,
+}
+
+/// 航空运输进项税扣除率（%）
+const FLIGHT_DEDUCTION_RATE_PERCENT: i64 = 9;
+
+impl AirTicketResponse {
+    /// 转换为 Invoice 模型
+    pub fn to_invoice(
+        &self,
+        file_path: Option<&str>,
+        file_type: Option<&str>,
+        raw_json: &str,
+    ) -> Invoice {
+        let wr = &self.words_result;
+        let fare = parse_money(wr.fare.as_deref());
+        let development_fund = parse_money(wr.dev_fund.as_deref());
+        let fuel_surcharge = parse_money(wr.fuel_surcharge.as_deref());
+        let other_tax = parse_money(wr.other_tax.as_deref());
+        let insurance = parse_money(wr.insurance.as_deref());
+
+        let total = parse_money(wr.ticket_rates.as_deref()).unwrap_or_else(|| {
+            [fare, development_fund, fuel_surcharge, other_tax, insurance]
+                .into_iter()
+                .flatten()
+                .sum()
+        });
+        // 计税依据为票价 + 燃油附加费，民航发展基金和保险费不计入
+        let taxable_base = match (fare, fuel_surcharge) {
+            (None, None) => None,
+            (fare, fuel) => Some(fare.unwrap_or(Money::ZERO) + fuel.unwrap_or(Money::ZERO)),
+        };
+        let deductible_tax =
+            taxable_base.map(|base| base.included_tax(FLIGHT_DEDUCTION_RATE_PERCENT));
+        let legs = Self::parse_legs(wr);
+
+        let mut invoice = Invoice::new(InvoiceType::FlightItinerary, total);
+
+        invoice.invoice_number = wr.ticket_number.clone();
+        invoice.invoice_date = parse_date(wr.issued_date.as_deref())
+            .or_else(|| legs.first().and_then(|leg| leg.date.clone()));
+        if let Some(warning) = invalid_date(wr.issued_date.as_deref()) {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
+        invoice.tax_amount = deductible_tax;
+        invoice.amount_without_tax = taxable_base
+            .zip(deductible_tax)
+            .map(|(base, tax)| base - tax);
+        invoice.seller_name = wr.issued_by.clone();
+        invoice.check_code = wr.ck.clone();
+        invoice.commodity_name = Self::route_summary(&legs);
+        invoice.extra_data = InvoiceExtra::FlightItinerary(FlightItineraryExtra {
+            passenger_name: wr.name.clone(),
+            id_number: wr.id_num.clone(),
+            legs,
+            fare,
+            development_fund,
+            fuel_surcharge,
+            other_tax,
+            insurance,
+            deductible_tax,
+        })
+        .to_json();
+        invoice.original_file_path = file_path.map(String::from);
+        invoice.file_type = file_type.map(String::from);
+        invoice.ocr_raw_response = Some(raw_json.to_string());
+
+        invoice
+    }
+
+    /// 按行组装航段；出发地缺失时沿用上一航段的目的地
+    fn parse_legs(wr: &AirTicketWordsResult) -> Vec<FlightLeg> {
+        let values =
+            |field: &Option<OcrWords>| field.as_ref().map(OcrWords::values).unwrap_or_default();
+        let from = values(&wr.starting_station);
+        let to = values(&wr.destination_station);
+        let flights = values(&wr.flight);
+        let dates = values(&wr.date);
+        let times = values(&wr.time);
+
+        let count = [to.len(), flights.len(), dates.len()]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+
+        let mut legs: Vec<FlightLeg> = Vec::with_capacity(count);
+        for i in 0..count {
+            let previous_to = legs.last().and_then(|leg| leg.to.clone());
+            legs.push(FlightLeg {
+                from: from.get(i).cloned().or(previous_to),
+                to: to.get(i).cloned(),
+                flight_number: flights.get(i).cloned(),
+                date: parse_date(dates.get(i).map(String::as_str)),
+                time: times.get(i).cloned(),
+            });
+        }
+        legs
+    }
+
+    /// 生成行程摘要，如 "CA1501 北京-上海"
+    fn route_summary(legs: &[FlightLeg]) -> Option<String> {
+        let parts: Vec<String> = legs
+            .iter()
+            .map(|leg| {
+                let route = [leg.from.as_deref(), leg.to.as_deref()]
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join("-");
+                match &leg.flight_number {
+                    Some(flight) => format!("{} {}", flight, route).trim().to_string(),
+                    None => route,
+                }
+            })
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("; "))
+        }
+    }
+}
+
+/// 通用机打发票 OCR 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenericInvoiceResponse {
+    #[serde(default)]
+    pub words_result: GenericInvoiceWordsResult,
+    pub words_result_num: Option<i32>,
+    pub log_id: Option<i64>,
+}
+
+/// 通用机打发票 OCR 字段
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GenericInvoiceWordsResult {
+    #[serde(rename = "InvoiceCode")]
+    pub invoice_code: Option<String>,
+    #[serde(rename = "InvoiceNum")]
+    pub invoice_num: Option<String>,
+    #[serde(rename = "InvoiceDate")]
+    pub invoice_date: Option<String>,
+    /// 发票种类，如 "通用机打发票"、"定额发票"
+    #[serde(rename = "InvoiceType")]
+    pub invoice_type: Option<String>,
+    #[serde(rename = "AmountInFiguers")]
+    pub amount_in_figures: Option<String>,
+    #[serde(rename = "AmountInWords")]
+    pub amount_in_words: Option<String>,
+    #[serde(rename = "SellerName")]
+    pub seller_name: Option<String>,
+    #[serde(rename = "SellerRegisterNum")]
+    pub seller_register_num: Option<String>,
+    #[serde(rename = "PurchaserName")]
+    pub purchaser_name: Option<String>,
+    #[serde(rename = "PurchaserRegisterNum")]
+    pub purchaser_register_num: Option<String>,
+    #[serde(rename = "CommodityName")]
+    pub commodity_name: Option<Vec<CommodityItem>>,
+    #[serde(rename = "CommodityAmount")]
+    pub commodity_amount: Option<Vec<CommodityItem>>,
+    #[serde(rename = "Province")]
+    pub province: Option<String>,
+    #[serde(rename = "City")]
+    pub city: Option<String>,
+}
+
+/// 文字在图片中的位置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordsLocation {
+    pub left: i32,
+    pub top: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl GenericInvoiceResponse {
+    /// 转换为 Invoice 模型，票种按票面关键字判断
+    pub fn to_invoice(
+        &self,
+        file_path: Option<&str>,
+        file_type: Option<&str>,
+        raw_json: &str,
+    ) -> Invoice {
+        let wr = &self.words_result;
+        let invoice_type = Self::detect_invoice_type(&self.keyword_text());
+        let total = parse_money(wr.amount_in_figures.as_deref()).unwrap_or(Money::ZERO);
+
+        let mut invoice = Invoice::new(invoice_type, total);
+
+        invoice.invoice_code = wr.invoice_code.clone();
+        invoice.invoice_number = wr.invoice_num.clone();
+        invoice.invoice_date = parse_date(wr.invoice_date.as_deref());
+        if let Some(warning) = invalid_date(wr.invoice_date.as_deref()) {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
+        invoice.buyer_name = wr.purchaser_name.clone();
+        invoice.buyer_tax_number = wr.purchaser_register_num.clone();
+        invoice.seller_name = wr.seller_name.clone();
+        invoice.seller_tax_number = wr.seller_register_num.clone();
+        invoice.commodity_name =
+            VatInvoiceResponse::join_commodity_names(wr.commodity_name.as_ref());
+        invoice.commodity_detail =
+            VatInvoiceResponse::commodity_to_json(wr.commodity_name.as_ref());
+        invoice.original_file_path = file_path.map(String::from);
+        invoice.file_type = file_type.map(String::from);
+        invoice.ocr_raw_response = Some(raw_json.to_string());
+
+        invoice
+    }
+
+    /// 按票面关键字判断票种（用于自动分类）
+    pub fn classify(&self) -> InvoiceType {
+        let text = self.keyword_text();
+        let has_any = |keywords: &[&str]| keywords.iter().any(|kw| text.contains(kw));
+
+        if text.contains("增值税") || text.contains("电子发票") {
+            if text.contains("专用发票") {
+                return InvoiceType::VatInvoice;
+            }
+            if text.contains("卷") {
+                return InvoiceType::VatRollInvoice;
+            }
+            if text.contains("电子") {
+                return InvoiceType::VatElectronicInvoice;
+            }
+            if text.contains("普通发票") {
+                return InvoiceType::VatCommonInvoice;
+            }
+        }
+        if has_any(&["中国铁路", "铁路电子客票", "检票", "车次"]) {
+            return InvoiceType::TrainTicket;
+        }
+        if has_any(&["行程单", "航班", "民航发展基金", "燃油附加费"]) {
+            return InvoiceType::FlightItinerary;
+        }
+        if has_any(&["出租", "TAXI", "候时", "里程"]) {
+            return InvoiceType::TaxiTicket;
+        }
+        Self::detect_invoice_type(&text)
+    }
+
+    /// 用于判断票种的文字：发票种类、买卖方名称和商品名称
+    fn keyword_text(&self) -> String {
+        let wr = &self.words_result;
+        [&wr.invoice_type, &wr.seller_name, &wr.purchaser_name]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .chain(
+                wr.commodity_name
+                    .iter()
+                    .flatten()
+                    .map(|item| item.word.as_str()),
+            )
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 按票面关键字识别票种：通行费、定额、卷式
+    fn detect_invoice_type(text: &str) -> InvoiceType {
+        if ["通行费", "过路费", "过桥费", "收费站", "高速公路"]
+            .iter()
+            .any(|kw| text.contains(kw))
+        {
+            InvoiceType::TollInvoice
+        } else if text.contains("定额") {
+            InvoiceType::QuotaInvoice
+        } else if text.contains("卷式") || text.contains("卷票") {
+            InvoiceType::VatRollInvoice
+        } else {
+            InvoiceType::Other
+        }
+    }
+}
+
+/// 智能财务票据识别（多票种混贴）响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleInvoiceResponse {
+    #[serde(default)]
+    pub words_result: Vec<MultipleInvoiceItem>,
+    pub words_result_num: Option<i32>,
+    pub log_id: Option<i64>,
+}
+
+/// 单张票据的识别结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleInvoiceItem {
+    /// 票种标识，如 vat_invoice、train_ticket
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub probability: Option<f64>,
+    pub left: Option<i32>,
+    pub top: Option<i32>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// 各票种字段，值为 [{"word": ...}] 列表
+    #[serde(default)]
+    pub result: serde_json::Value,
+}
+
+impl MultipleInvoiceItem {
+    /// 票种标识转换为发票类型
+    pub fn invoice_type(&self) -> InvoiceType {
+        match self.kind.as_str() {
+            "vat_invoice" => InvoiceType::VatCommonInvoice,
+            "roll_normal_invoice" => InvoiceType::VatRollInvoice,
+            "train_ticket" => InvoiceType::TrainTicket,
+            "taxi_receipt" => InvoiceType::TaxiTicket,
+            "air_ticket" => InvoiceType::FlightItinerary,
+            "toll_invoice" => InvoiceType::TollInvoice,
+            "quota_invoice" => InvoiceType::QuotaInvoice,
+            _ => InvoiceType::Other,
+        }
+    }
+
+    /// 转换为 Invoice 模型，并记录票据在原图中的位置
+    pub fn to_invoice(&self, file_path: Option<&str>, file_type: Option<&str>) -> Invoice {
+        let raw_json = serde_json::to_string(self).unwrap_or_default();
+        let invoice_type = self.invoice_type();
+
+        let mapped = match invoice_type {
+            InvoiceType::VatCommonInvoice | InvoiceType::VatRollInvoice => self
+                .words_result::<VatInvoiceWordsResult>(&["Commodity"])
+                .map(|words_result| {
+                    VatInvoiceResponse {
+                        words_result,
+                        words_result_num: None,
+                        log_id: None,
+                    }
+                    .to_invoice(file_path, file_type, &raw_json, None)
+                }),
+            InvoiceType::TrainTicket => {
+                self.words_result::<TrainTicketWordsResult>(&[])
+                    .map(|words_result| {
+                        TrainTicketResponse {
+                            words_result,
+                            words_result_num: None,
+                            log_id: None,
+                        }
+                        .to_invoice(file_path, file_type, &raw_json)
+                    })
+            }
+            InvoiceType::TaxiTicket => {
+                self.words_result::<TaxiReceiptWordsResult>(&[])
+                    .map(|words_result| {
+                        TaxiReceiptResponse {
+                            words_result,
+                            words_result_num: None,
+                            log_id: None,
+                        }
+                        .to_invoice(file_path, file_type, &raw_json)
+                    })
+            }
+            InvoiceType::FlightItinerary => self
+                .words_result::<AirTicketWordsResult>(&[
+                    "starting_station",
+                    "destination_station",
+                    "flight",
+                    "date",
+                    "time",
+                    "carrier",
+                ])
+                .map(|words_result| {
+                    AirTicketResponse {
+                        words_result,
+                        words_result_num: None,
+                        log_id: None,
+                    }
+                    .to_invoice(file_path, file_type, &raw_json)
+                }),
+            _ => None,
+        };
+
+        let mut invoice = mapped.unwrap_or_else(|| {
+            let mut invoice = self.to_basic_invoice(invoice_type);
+            invoice.original_file_path = file_path.map(String::from);
+            invoice.file_type = file_type.map(String::from);
+            invoice.ocr_raw_response = Some(raw_json.clone());
+            invoice
+        });
+        invoice.bounding_box = self.bounding_box();
+
+        invoice
+    }
+
+    /// 票据位置 JSON
+    pub fn bounding_box(&self) -> Option<String> {
+        match (self.left, self.top, self.width, self.height) {
+            (Some(left), Some(top), Some(width), Some(height)) => {
+                serde_json::to_string(&WordsLocation {
+                    left,
+                    top,
+                    width,
+                    height,
+                })
+                .ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// 将 `result` 中的 [{"word": ...}] 列表展开为文本后解析为指定票种字段，
+    /// `list_fields` 中列出的字段（按前缀匹配）保留为列表
+    fn words_result<T: serde::de::DeserializeOwned>(&self, list_fields: &[&str]) -> Option<T> {
+        let fields = self.result.as_object()?;
+        let flattened: serde_json::Map<String, serde_json::Value> = fields
+            .iter()
+            .map(|(key, value)| {
+                let keep_list = list_fields.iter().any(|prefix| key.starts_with(prefix));
+                let value = match value {
+                    serde_json::Value::Array(_) if keep_list => value.clone(),
+                    serde_json::Value::Array(items) => serde_json::Value::String(join_words(items)),
+                    other => other.clone(),
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        serde_json::from_value(serde_json::Value::Object(flattened)).ok()
+    }
+
+    /// 通行费、定额等票种的通用字段映射
+    fn to_basic_invoice(&self, invoice_type: InvoiceType) -> Invoice {
+        let field = |keys: &[&str]| -> Option<String> {
+            let fields = self.result.as_object()?;
+            keys.iter().find_map(|key| match fields.get(*key)? {
+                serde_json::Value::Array(items) => {
+                    Some(join_words(items)).filter(|word| !word.is_empty())
+                }
+                serde_json::Value::String(word) => Some(word.clone()),
+                _ => None,
+            })
+        };
+
+        let total = parse_money(
+            field(&[
+                "AmountInFiguers",
+                "TotalAmount",
+                "Fare",
+                "invoice_rate",
+                "Amount",
+                "amount",
+            ])
+            .as_deref(),
+        )
+        .unwrap_or(Money::ZERO);
+
+        let mut invoice = Invoice::new(invoice_type, total);
+        invoice.invoice_code = field(&["InvoiceCode", "invoice_code"]);
+        invoice.invoice_number = field(&["InvoiceNum", "invoice_number"]);
+        let date = field(&["InvoiceDate", "Date", "date"]);
+        invoice.invoice_date = parse_date(date.as_deref());
+        if let Some(warning) = invalid_date(date.as_deref()) {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
+        invoice.seller_name = field(&["SellerName", "seller_name"]);
+        invoice
+    }
+}
+
+/// 拼接 [{"word": ...}] 列表中的文字
+fn join_words(items: &[serde_json::Value]) -> String {
+    items
+        .iter()
+        .filter_map(|item| item.get("word").and_then(|word| word.as_str()))
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// 解析里程等非金额数值，去除单位
+pub(crate) fn parse_amount(s: Option<&str>) -> Option<f64> {
+    s.and_then(|v| {
+        let cleaned: String = v
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+            .collect();
+        cleaned.parse().ok()
+    })
+}
+
+/// 解析金额字符串为 `Money`
+pub(crate) fn parse_money(s: Option<&str>) -> Option<Money> {
+    s.and_then(Money::parse)
+}
+
+/// 票面有日期但无法识别时返回 `InvalidDate` 提示，原值为空时返回 `None`
+pub(crate) fn invalid_date(date_str: Option<&str>) -> Option<InvoiceWarning> {
+    let value = date_str.map(str::trim).filter(|value| !value.is_empty())?;
+    parse_date(Some(value))
+        .is_none()
+        .then(|| InvoiceWarning::InvalidDate {
+            value: value.to_string(),
+        })
+}
+
+/// 票面上出现过的日期格式，月、日可以不补零；`20240105` 这类紧凑格式单独处理
+const DATE_FORMATS: [&str; 4] = ["%Y年%m月%d日", "%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"];
+
+/// 解析票面日期为 YYYY-MM-DD 形式，不存在的日期（如 2 月 30 日）返回 `None`
+///
+/// 支持 `2024年1月5日`、`2024-01-05`、`2024/1/5`、`2024.01.05`、`20240105`，可带时间。
+pub(crate) fn parse_date(date_str: Option<&str>) -> Option<String> {
+    let s = date_str?.trim();
+    // 只保留日期部分，年月日之间的空格一并去掉
+    let date: String = match s.find('日') {
+        Some(end) => s[..end + '日'.len_utf8()]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect(),
+        None => s
+            .split(|c: char| c.is_whitespace() || c == 'T')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    };
+
+    let parsed = if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) {
+        NaiveDate::from_ymd_opt(
+            date[..4].parse().ok()?,
+            date[4..6].parse().ok()?,
+            date[6..].parse().ok()?,
+        )
+    } else {
+        DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(&date, format).ok())
+    };
+    parsed
+        .filter(|d| (1900..=9999).contains(&d.year()))
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_train_ticket_to_invoice() {
+        let json = crate::test_support::BAIDU_TRAIN_TICKET_JSON;
+        let response: TrainTicketResponse = serde_json::from_str(json).unwrap();
+        let invoice = response.to_invoice(None, None, json);
+
+        assert_eq!(invoice.invoice_type, InvoiceType::TrainTicket);
+        assert_eq!(invoice.total_amount, Money::from_fen(55300));
+        assert_eq!(invoice.invoice_number.as_deref(), Some("E071564"));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-01-15"));
+        assert_eq!(
+            invoice.commodity_name.as_deref(),
+            Some("G101 北京南-上海虹桥")
+        );
+
+        let extra: InvoiceExtra =
+            serde_json::from_str(invoice.extra_data.as_deref().unwrap()).unwrap();
+        let InvoiceExtra::TrainTicket(extra) = extra else {
+            panic!("unexpected {:?}", extra);
+        };
+        assert_eq!(extra.departure_station.as_deref(), Some("北京南"));
+        assert_eq!(extra.arrival_station.as_deref(), Some("上海虹桥"));
+        assert_eq!(extra.train_number.as_deref(), Some("G101"));
+        assert_eq!(extra.seat_class.as_deref(), Some("二等座"));
+        assert_eq!(extra.seat_number.as_deref(), Some("05车12F号"));
+        assert_eq!(extra.passenger_name.as_deref(), Some("张三"));
+        assert_eq!(extra.departure_date.as_deref(), Some("2024-01-15"));
+        assert_eq!(extra.departure_time.as_deref(), Some("06:36"));
+        assert_eq!(extra.ticket_price, Some(Money::from_fen(55300)));
+    }
+
+    #[test]
+    fn test_taxi_receipt_time_range_and_fare() {
+        let json = r#"{
+            "words_result": {
+                "InvoiceCode": "111001881001",
+                "InvoiceNum": "52718532",
+                "TaxiNum": "京B·T1234",
+                "Date": "2024-03-08",
+                "Time": "18:00-18:14",
+                "PricePerkm": "2.30元",
+                "Distance": "4.3公里",
+                "Fare": "¥18.00元",
+                "TotalFare": "",
+                "City": "北京市"
+            }
+        }"#;
+        let response: TaxiReceiptResponse = serde_json::from_str(json).unwrap();
+        let invoice = response.to_invoice(None, None, json);
+
+        // 实收金额为空时取车费
+        assert_eq!(invoice.total_amount, Money::from_fen(1800));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-03-08"));
+        assert_eq!(
+            invoice.commodity_name.as_deref(),
+            Some("出租车费 北京市 4.3公里")
+        );
+        let Some(InvoiceExtra::TaxiTicket(extra)) = invoice
+            .extra_data
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+        else {
+            panic!("unexpected {:?}", invoice.extra_data);
+        };
+        assert_eq!(extra.pickup_time.as_deref(), Some("18:00"));
+        assert_eq!(extra.dropoff_time.as_deref(), Some("18:14"));
+        assert_eq!(extra.mileage, Some(4.3));
+        assert_eq!(extra.fare, Some(Money::from_fen(1800)));
+
+        // 有独立的上下车时间字段时不拆分时段
+        let wr = TaxiReceiptWordsResult {
+            time: Some("18:00-18:14".to_string()),
+            pickup_time: Some("17:58".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            TaxiReceiptResponse::split_time_range(&wr),
+            (Some("17:58".to_string()), None)
+        );
+    }
+
+    #[test]
+    fn test_air_ticket_deductible_tax() {
+        let json = r#"{
+            "words_result": {
+                "name": "张三",
+                "starting_station": "北京",
+                "destination_station": [{"row": "1", "word": "上海"}, {"row": "2", "word": "广州"}],
+                "flight": [{"row": "1", "word": "CA1501"}, {"row": "2", "word": "MU5301"}],
+                "date": [{"row": "1", "word": "2024年03月01日"}, {"row": "2", "word": "2024年03月05日"}],
+                "fare": "1090.00",
+                "dev_fund": "100.00",
+                "fuel_surcharge": "218.00",
+                "insurance": "30.00"
+            }
+        }"#;
+        let response: AirTicketResponse = serde_json::from_str(json).unwrap();
+        let invoice = response.to_invoice(None, None, json);
+
+        assert_eq!(invoice.total_amount, Money::from_fen(143800));
+        assert_eq!(invoice.tax_amount, Some(Money::from_fen(10800)));
+        assert_eq!(invoice.amount_without_tax, Some(Money::from_fen(120000)));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-03-01"));
+        assert_eq!(
+            invoice.commodity_name.as_deref(),
+            Some("CA1501 北京-上海; MU5301 上海-广州")
+        );
+    }
+
+    #[test]
+    fn test_generic_toll_invoice() {
+        let json = r#"{
+            "log_id": 1682563453282418178,
+            "words_result_num": 9,
+            "words_result": {
+                "InvoiceType": "通用机打发票",
+                "InvoiceCode": "133001920011",
+                "InvoiceNum": "01234567",
+                "InvoiceDate": "2024年03月05日",
+                "AmountInFiguers": "45.00",
+                "AmountInWords": "肆拾伍元整",
+                "SellerName": "浙江沪杭甬高速公路股份有限公司",
+                "PurchaserName": "杭州示例科技有限公司",
+                "CommodityName": [{"row": "1", "word": "通行费"}],
+                "CommodityAmount": [{"row": "1", "word": "45.00"}]
+            }
+        }"#;
+        let response: GenericInvoiceResponse = serde_json::from_str(json).unwrap();
+        let invoice = response.to_invoice(None, None, json);
+
+        assert_eq!(invoice.invoice_type, InvoiceType::TollInvoice);
+        assert_eq!(invoice.total_amount, Money::from_fen(4500));
+        assert_eq!(invoice.invoice_code.as_deref(), Some("133001920011"));
+        assert_eq!(invoice.invoice_number.as_deref(), Some("01234567"));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-03-05"));
+        assert_eq!(
+            invoice.seller_name.as_deref(),
+            Some("浙江沪杭甬高速公路股份有限公司")
+        );
+        assert_eq!(invoice.buyer_name.as_deref(), Some("杭州示例科技有限公司"));
+        assert_eq!(invoice.commodity_name.as_deref(), Some("通行费"));
+
+        let quota: GenericInvoiceResponse =
+            serde_json::from_str(r#"{"words_result": {"InvoiceType": "定额发票"}}"#).unwrap();
+        assert_eq!(
+            quota.to_invoice(None, None, "").invoice_type,
+            InvoiceType::QuotaInvoice
+        );
+    }
+
+    #[test]
+    fn test_generic_keyword_classify() {
+        let classify = |words_result: &str| {
+            serde_json::from_str::<GenericInvoiceResponse>(&format!(
+                r#"{{"words_result": {}}}"#,
+                words_result
+            ))
+            .unwrap()
+            .classify()
+        };
+
+        assert_eq!(
+            classify(r#"{"InvoiceType": "增值税电子普通发票"}"#),
+            InvoiceType::VatElectronicInvoice
+        );
+        assert_eq!(
+            classify(r#"{"SellerName": "中国铁路北京局集团有限公司"}"#),
+            InvoiceType::TrainTicket
+        );
+        assert_eq!(
+            classify(r#"{"CommodityName": [{"row": "1", "word": "民航发展基金"}]}"#),
+            InvoiceType::FlightItinerary
+        );
+        assert_eq!(
+            classify(r#"{"InvoiceType": "卷式发票"}"#),
+            InvoiceType::VatRollInvoice
+        );
+        assert_eq!(classify("{}"), InvoiceType::Other);
+    }
+
+    #[test]
+    fn test_vat_invoice_qr_code_overrides_ocr() {
+        let response: VatInvoiceResponse =
+            serde_json::from_str(crate::test_support::BAIDU_VAT_INVOICE_JSON).unwrap();
+        let qr_code = InvoiceQrCode::parse(
+            "01,04,3300173130,05968313,1000.00,20240115,12345678901234567890,A1B2,",
+        )
+        .unwrap();
+        let invoice = response.to_invoice(None, None, "", Some(&qr_code));
+
+        assert_eq!(invoice.invoice_number.as_deref(), Some("05968313"));
+        assert_eq!(invoice.check_code.as_deref(), Some("12345678901234567890"));
+        let warnings: Vec<InvoiceWarning> =
+            serde_json::from_str(invoice.warnings.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            warnings,
+            vec![InvoiceWarning::QrMismatch {
+                field: "invoice_number".to_string(),
+                ocr_value: "05968312".to_string(),
+                qr_value: "05968313".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        for raw in [
+            "2024年1月5日",
+            "2024 年 01 月 05 日",
+            "2024-1-5",
+            "2024/01/05",
+            "2024.1.05",
+            "20240105",
+            "2024-01-05 14:08:00",
+            "2024年01月05日 14:08",
+        ] {
+            assert_eq!(
+                parse_date(Some(raw)).as_deref(),
+                Some("2024-01-05"),
+                "{}",
+                raw
+            );
+        }
+        assert_eq!(parse_date(Some("2023-02-29")), None);
+        assert_eq!(parse_date(Some("2024年13月01日")), None);
+        assert_eq!(parse_date(Some("无")), None);
+    }
+
+    #[test]
+    fn test_invalid_date_warning() {
+        let invalid = || {
+            Some(
+                InvoiceWarning::list_to_json(&[InvoiceWarning::InvalidDate {
+                    value: "2024年02月30日".to_string(),
+                }])
+                .unwrap(),
+            )
+        };
+
+        let json = crate::test_support::BAIDU_TRAIN_TICKET_JSON
+            .replace("2024年01月15日", "2024年02月30日");
+        let response: TrainTicketResponse = serde_json::from_str(&json).unwrap();
+        let invoice = response.to_invoice(None, None, &json);
+        assert_eq!(invoice.invoice_date, None);
+        assert_eq!(invoice.warnings, invalid());
+
+        let json =
+            crate::test_support::BAIDU_VAT_INVOICE_JSON.replace("2024年01月15日", "2024年02月30日");
+        let response: VatInvoiceResponse = serde_json::from_str(&json).unwrap();
+        let invoice = response.to_invoice(None, None, &json, None);
+        assert_eq!(invoice.invoice_date, None);
+        assert_eq!(invoice.warnings, invalid());
+
+        // 二维码补上日期时不再提示
+        let qr_code = InvoiceQrCode::parse(
+            "01,04,3300173130,05968312,1000.00,20240115,12345678901234567890,A1B2,",
+        )
+        .unwrap();
+        let invoice = response.to_invoice(None, None, &json, Some(&qr_code));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-01-15"));
+        assert_eq!(invoice.warnings, None);
+
+        assert_eq!(invalid_date(Some(" ")), None);
+        assert_eq!(invalid_date(Some("2024-01-15")), None);
+    }
+}
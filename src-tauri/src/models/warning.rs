@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use super::duplicate::DuplicateReason;
+use super::money::Money;
+
+/// 识别结果的提示信息，序列化后存入 `Invoice.warnings`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InvoiceWarning {
+    /// 二维码与 OCR 结果不一致（已采用二维码的值）
+    QrMismatch {
+        field: String,
+        ocr_value: String,
+        qr_value: String,
+    },
+    /// 与已有发票重复（按配置仍保存）
+    Duplicate {
+        existing_id: String,
+        reason: DuplicateReason,
+    },
+    /// 不含税金额 + 税额与价税合计不符
+    AmountMismatch {
+        amount_without_tax: Money,
+        tax_amount: Money,
+        total_amount: Money,
+    },
+    /// 商品明细金额之和与不含税合计金额不符
+    LineSumMismatch { line_sum: Money, expected: Money },
+    /// 金额为负数（红字发票也会出现）
+    NegativeAmount { field: String, amount: Money },
+    /// 价税合计为零，通常是金额未识别出来
+    ZeroTotal,
+    /// 明细税率不在法定税率范围内
+    InvalidTaxRate { row: Option<String>, rate: String },
+    /// 税号未通过校验，附带替换形近字符后可通过校验的候选值
+    InvalidTaxNumber {
+        field: String,
+        value: String,
+        suggestions: Vec<String>,
+    },
+    /// 购买方不是本公司
+    BuyerMismatch {
+        buyer_name: Option<String>,
+        buyer_tax_number: Option<String>,
+    },
+    /// 开票日期无法识别或不存在，已清空
+    InvalidDate { value: String },
+    /// 税号中的 OCR 易错字符已自动修正
+    TaxNumberCorrected {
+        field: String,
+        original: String,
+        corrected: String,
+    },
+    /// 票种分类接口调用失败，已改用关键字判断
+    ClassifyFallback { error: String },
+}
+
+impl InvoiceWarning {
+    /// 转换为 JSON 字符串，没有提示时返回 `None`
+    pub fn list_to_json(warnings: &[InvoiceWarning]) -> Option<String> {
+        if warnings.is_empty() {
+            None
+        } else {
+            serde_json::to_string(warnings).ok()
+        }
+    }
+
+    /// 是否为校验产生的提示，重新校验时会被替换（自动修正的记录另行处理）
+    pub fn is_validation(&self) -> bool {
+        matches!(
+            self,
+            InvoiceWarning::AmountMismatch { .. }
+                | InvoiceWarning::LineSumMismatch { .. }
+                | InvoiceWarning::NegativeAmount { .. }
+                | InvoiceWarning::ZeroTotal
+                | InvoiceWarning::InvalidTaxRate { .. }
+                | InvoiceWarning::InvalidTaxNumber { .. }
+        )
+    }
+
+    /// 解析已序列化的提示列表，无法解析时视为空
+    pub fn list_from_json(json: Option<&str>) -> Vec<InvoiceWarning> {
+        json.and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    /// 向已序列化的提示列表追加一条
+    pub fn append_to(json: &mut Option<String>, warning: InvoiceWarning) {
+        let mut warnings = Self::list_from_json(json.as_deref());
+        warnings.push(warning);
+        *json = Self::list_to_json(&warnings);
+    }
+
+    /// 从已序列化的提示列表中移除满足条件的提示
+    pub fn remove_from(json: &mut Option<String>, f: impl Fn(&InvoiceWarning) -> bool) {
+        let mut warnings = Self::list_from_json(json.as_deref());
+        warnings.retain(|warning| !f(warning));
+        *json = Self::list_to_json(&warnings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_from() {
+        let mut json = None;
+        InvoiceWarning::append_to(
+            &mut json,
+            InvoiceWarning::InvalidDate {
+                value: "2024年13月1日".to_string(),
+            },
+        );
+        InvoiceWarning::append_to(&mut json, InvoiceWarning::ZeroTotal);
+
+        InvoiceWarning::remove_from(&mut json, |w| {
+            matches!(w, InvoiceWarning::InvalidDate { .. })
+        });
+        assert_eq!(
+            InvoiceWarning::list_from_json(json.as_deref()),
+            vec![InvoiceWarning::ZeroTotal]
+        );
+        InvoiceWarning::remove_from(&mut json, |w| *w == InvoiceWarning::ZeroTotal);
+        assert_eq!(json, None);
+    }
+}
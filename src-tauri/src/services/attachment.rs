@@ -0,0 +1,218 @@
+//! 原始文件归档：按内容 SHA-256 保存在应用数据目录下，避免用户移动或删除原文件后记录失效
+
+use crate::db::{self, attachment_repo};
+use crate::error::AppError;
+use crate::models::attachment::{Attachment, AttachmentContent};
+use crate::services::file::FileService;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct AttachmentStore {
+    root: PathBuf,
+}
+
+/// 归档结果
+pub struct StoredAttachment {
+    pub attachment: Attachment,
+    /// 本次是否新写入了附件文件（内容已归档过时为 false）
+    pub created: bool,
+}
+
+impl AttachmentStore {
+    /// 使用应用数据目录下的 attachments 目录（与 data.db 同级）
+    pub fn new() -> Result<Self, AppError> {
+        Ok(Self::with_root(db::app_data_dir()?.join("attachments")))
+    }
+
+    /// 使用指定目录
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 归档文件并记录元数据，相同内容只保存一份
+    pub fn store(&self, path: &Path) -> Result<Attachment, AppError> {
+        let conn = db::get_connection()?;
+        Ok(self.store_in(&conn, path)?.attachment)
+    }
+
+    /// 在指定连接（如保存发票的事务）上归档文件
+    ///
+    /// 写文件与清理附件都在持有连接时进行，避免清理时删掉刚归档、尚未被发票引用的文件。
+    /// 事务回滚时应调用 [`Self::discard`] 删除新写入的文件。
+    pub fn store_in(&self, conn: &Connection, path: &Path) -> Result<StoredAttachment, AppError> {
+        let mime_type = FileService::validate_file_type(path)?
+            .mime_type()
+            .to_string();
+        let data =
+            fs::read(path).map_err(|e| AppError::FileProcess(format!("读取文件失败: {}", e)))?;
+        let hash = Self::hash_bytes(&data);
+
+        let target = self.path_of(&hash);
+        let created = !target.exists();
+        if created {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // 先写临时文件再改名，避免中断时留下不完整的附件
+            let temp = target.with_extension("tmp");
+            fs::write(&temp, &data)?;
+            fs::rename(&temp, &target)?;
+        }
+
+        let attachment = Attachment {
+            hash,
+            size: data.len() as u64,
+            mime_type,
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let stored = StoredAttachment {
+            attachment,
+            created,
+        };
+        if let Err(e) = attachment_repo::insert_if_absent(conn, &stored.attachment) {
+            self.discard(&stored);
+            return Err(e);
+        }
+        Ok(stored)
+    }
+
+    /// 删除 [`Self::store_in`] 新写入的附件文件（已有的附件不受影响）
+    pub fn discard(&self, stored: &StoredAttachment) {
+        if stored.created {
+            fs::remove_file(self.path_of(&stored.attachment.hash)).ok();
+        }
+    }
+
+    /// 计算文件内容的 SHA-256
+    pub fn hash_file(path: &Path) -> Result<String, AppError> {
+        let data =
+            fs::read(path).map_err(|e| AppError::FileProcess(format!("读取文件失败: {}", e)))?;
+        Ok(Self::hash_bytes(&data))
+    }
+
+    fn hash_bytes(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    /// 读取附件内容
+    pub fn read(&self, hash: &str) -> Result<AttachmentContent, AppError> {
+        let not_found = || AppError::FileProcess(format!("附件不存在: {}", hash));
+        if !Self::is_valid_hash(hash) {
+            return Err(not_found());
+        }
+        let attachment = attachment_repo::find_by_hash(hash)?.ok_or_else(not_found)?;
+        let data = fs::read(self.path_of(hash))
+            .map_err(|e| AppError::FileProcess(format!("读取附件失败: {}", e)))?;
+
+        Ok(AttachmentContent {
+            attachment,
+            data: STANDARD.encode(data),
+        })
+    }
+
+    /// 清理没有发票引用的附件，返回清理的数量
+    pub fn collect_garbage(&self) -> Result<u32, AppError> {
+        // 删除文件期间持有连接，避免与正在归档同一内容的保存交错
+        let conn = db::get_connection()?;
+        let hashes = attachment_repo::delete_unreferenced(&conn)?;
+        for hash in &hashes {
+            match fs::remove_file(self.path_of(hash)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(hashes.len() as u32)
+    }
+
+    /// 附件的保存路径（按哈希前两位分目录）
+    fn path_of(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+
+    fn is_valid_hash(hash: &str) -> bool {
+        hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::invoice_repo;
+    use crate::models::invoice::{Invoice, InvoiceType};
+    use crate::models::money::Money;
+
+    #[test]
+    fn test_store_deduplicates_and_collects_garbage() {
+        db::init_test_database().unwrap();
+        let dir = std::env::temp_dir()
+            .join("tax-recognize-tests")
+            .join(format!("attachments-{}", uuid::Uuid::new_v4()));
+        let store = AttachmentStore::with_root(dir.join("store"));
+
+        let mut png = Vec::new();
+        image::DynamicImage::new_luma8(20, 20)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let (first, second) = (dir.join("a.png"), dir.join("b.png"));
+        fs::write(&first, &png).unwrap();
+        fs::write(&second, &png).unwrap();
+
+        let attachment = store.store(&first).unwrap();
+        assert_eq!(store.store(&second).unwrap().hash, attachment.hash);
+        assert_eq!(attachment.mime_type, "image/png");
+        fs::remove_file(&first).unwrap();
+
+        let mut invoice = Invoice::new(InvoiceType::Other, Money::from_fen(100));
+        invoice.attachment_hash = Some(attachment.hash.clone());
+        invoice_repo::insert(&invoice).unwrap();
+        store.collect_garbage().unwrap();
+        let content = store.read(&attachment.hash).unwrap();
+        assert_eq!(content.attachment.file_name.as_deref(), Some("a.png"));
+        assert_eq!(STANDARD.decode(content.data).unwrap(), png);
+
+        invoice_repo::delete(&invoice.id).unwrap();
+        store.collect_garbage().unwrap();
+        assert!(!store.path_of(&attachment.hash).exists());
+        assert!(store.read(&attachment.hash).is_err());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_discard_on_rollback() {
+        db::init_test_database().unwrap();
+        let dir = std::env::temp_dir()
+            .join("tax-recognize-tests")
+            .join(format!("attachments-{}", uuid::Uuid::new_v4()));
+        let store = AttachmentStore::with_root(dir.join("store"));
+
+        let mut png = Vec::new();
+        image::DynamicImage::new_luma8(21, 21)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("c.png");
+        fs::write(&file, &png).unwrap();
+
+        let mut hash = String::new();
+        let result: Result<(), AppError> = db::with_transaction(|conn| {
+            let stored = store.store_in(conn, &file)?;
+            assert!(stored.created);
+            hash = stored.attachment.hash.clone();
+            store.discard(&stored);
+            Err(AppError::FileProcess("回滚".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(!store.path_of(&hash).exists());
+        assert!(attachment_repo::find_by_hash(&hash).unwrap().is_none());
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
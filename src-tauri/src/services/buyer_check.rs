@@ -0,0 +1,145 @@
+//! 核对发票购买方是否为本公司主体
+
+use crate::db::config_repo;
+use crate::error::AppError;
+use crate::models::company::{BuyerCheck, BuyerPolicy, CompanyEntity};
+use crate::models::config::config_keys;
+use crate::models::invoice::Invoice;
+use crate::models::warning::InvoiceWarning;
+
+#[derive(Debug, Clone, Default)]
+pub struct BuyerChecker {
+    entities: Vec<CompanyEntity>,
+}
+
+impl BuyerChecker {
+    pub fn new(entities: Vec<CompanyEntity>) -> Self {
+        Self { entities }
+    }
+
+    /// 读取配置的公司主体，未配置时不核对
+    pub fn from_config() -> Result<Self, AppError> {
+        let entities = match config_repo::get_config(config_keys::COMPANY_ENTITIES)? {
+            Some(value) if !value.trim().is_empty() => serde_json::from_str(&value)
+                .map_err(|e| AppError::Config(format!("公司主体配置格式错误: {}", e)))?,
+            _ => Vec::new(),
+        };
+        Ok(Self::new(entities))
+    }
+
+    /// 读取配置的处理方式，未配置时为 warn
+    pub fn policy_from_config() -> Result<BuyerPolicy, AppError> {
+        match config_repo::get_config(config_keys::BUYER_POLICY)? {
+            Some(value) => BuyerPolicy::parse(&value)
+                .ok_or_else(|| AppError::Config(format!("不支持的购买方处理方式: {}", value))),
+            None => Ok(BuyerPolicy::default()),
+        }
+    }
+
+    /// 核对购买方，未配置公司主体时返回 `None`
+    ///
+    /// 票面有税号时税号须一致，有名称时名称也须一致。
+    pub fn check(&self, invoice: &Invoice) -> Option<BuyerCheck> {
+        if self.entities.is_empty() {
+            return None;
+        }
+        let name = invoice.buyer_name.as_deref().map(normalize_name);
+        let tax_number = invoice
+            .buyer_tax_number
+            .as_deref()
+            .map(normalize_tax_number);
+        let name = name.filter(|s| !s.is_empty());
+        let tax_number = tax_number.filter(|s| !s.is_empty());
+        if name.is_none() && tax_number.is_none() {
+            return Some(BuyerCheck::Missing);
+        }
+
+        let matched = self.entities.iter().any(|entity| {
+            name.as_ref()
+                .is_none_or(|name| *name == normalize_name(&entity.name))
+                && tax_number.as_ref().is_none_or(|tax_number| {
+                    *tax_number == normalize_tax_number(&entity.tax_number)
+                })
+        });
+        Some(if matched {
+            BuyerCheck::Matched
+        } else {
+            BuyerCheck::Mismatch
+        })
+    }
+
+    /// 核对并记录到 `invoice.buyer_check`，不一致时替换原有的购买方提示
+    pub fn apply(&self, invoice: &mut Invoice) {
+        invoice.buyer_check = self.check(invoice);
+
+        let mut warnings: Vec<InvoiceWarning> =
+            InvoiceWarning::list_from_json(invoice.warnings.as_deref())
+                .into_iter()
+                .filter(|warning| !matches!(warning, InvoiceWarning::BuyerMismatch { .. }))
+                .collect();
+        if invoice.buyer_check == Some(BuyerCheck::Mismatch) {
+            warnings.push(InvoiceWarning::BuyerMismatch {
+                buyer_name: invoice.buyer_name.clone(),
+                buyer_tax_number: invoice.buyer_tax_number.clone(),
+            });
+        }
+        invoice.warnings = InvoiceWarning::list_to_json(&warnings);
+    }
+}
+
+/// 去除空白，统一全角括号
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '（' => '(',
+            '）' => ')',
+            c => c,
+        })
+        .collect()
+}
+
+fn normalize_tax_number(tax_number: &str) -> String {
+    tax_number
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::invoice::InvoiceType;
+    use crate::models::money::Money;
+
+    #[test]
+    fn test_check_buyer() {
+        let checker = BuyerChecker::new(vec![CompanyEntity {
+            name: "杭州示例科技有限公司（滨江分公司）".to_string(),
+            tax_number: "91330108MA2AXXXX1K".to_string(),
+        }]);
+        let mut invoice = Invoice::new(InvoiceType::VatInvoice, Money::from_fen(100));
+        assert_eq!(checker.check(&invoice), Some(BuyerCheck::Missing));
+
+        invoice.buyer_name = Some("杭州示例科技有限公司(滨江分公司)".to_string());
+        invoice.buyer_tax_number = Some("91330108ma2axxxx1k".to_string());
+        assert_eq!(checker.check(&invoice), Some(BuyerCheck::Matched));
+
+        invoice.buyer_tax_number = Some("91330108MA2AYYYY1K".to_string());
+        checker.apply(&mut invoice);
+        assert_eq!(invoice.buyer_check, Some(BuyerCheck::Mismatch));
+        assert!(invoice
+            .warnings
+            .as_deref()
+            .is_some_and(|w| w.contains("buyer_mismatch")));
+
+        // 修正后重新核对，提示随之移除
+        invoice.buyer_tax_number = Some("91330108MA2AXXXX1K".to_string());
+        checker.apply(&mut invoice);
+        assert_eq!(invoice.buyer_check, Some(BuyerCheck::Matched));
+        assert_eq!(invoice.warnings, None);
+
+        assert_eq!(BuyerChecker::default().check(&invoice), None);
+    }
+}
@@ -0,0 +1,111 @@
+//! 重复发票检测：依次按发票代码 + 号码、原始文件哈希、销售方 + 日期 + 金额匹配已有记录
+
+use crate::db::{config_repo, invoice_repo};
+use crate::error::AppError;
+use crate::models::config::config_keys;
+use crate::models::duplicate::{DuplicatePolicy, DuplicateReason};
+use crate::models::invoice::Invoice;
+
+pub struct DuplicateDetector;
+
+impl DuplicateDetector {
+    /// 读取配置的处理方式，未配置时为 warn
+    pub fn policy_from_config() -> Result<DuplicatePolicy, AppError> {
+        match config_repo::get_config(config_keys::DUPLICATE_POLICY)? {
+            Some(value) => DuplicatePolicy::parse(&value)
+                .ok_or_else(|| AppError::Config(format!("不支持的重复发票处理方式: {}", value))),
+            None => Ok(DuplicatePolicy::default()),
+        }
+    }
+
+    /// 查找与发票重复的已有记录，`file_hash` 为原始文件的 SHA-256
+    pub fn find(
+        invoice: &Invoice,
+        file_hash: Option<&str>,
+    ) -> Result<Option<(String, DuplicateReason)>, AppError> {
+        if let Some(number) = non_empty(&invoice.invoice_number) {
+            if let Some(id) =
+                invoice_repo::find_id_by_code_number(non_empty(&invoice.invoice_code), number)?
+            {
+                return Ok(Some((id, DuplicateReason::CodeNumber)));
+            }
+        }
+
+        if let Some(hash) = file_hash {
+            if let Some(id) = invoice_repo::find_id_by_attachment(
+                hash,
+                invoice.page_number,
+                invoice.bounding_box.as_deref(),
+            )? {
+                return Ok(Some((id, DuplicateReason::FileHash)));
+            }
+        }
+
+        // 双方都有号码时已按号码比对过，不再模糊匹配
+        if let (Some(seller), Some(date)) = (
+            non_empty(&invoice.seller_name),
+            non_empty(&invoice.invoice_date),
+        ) {
+            if let Some(id) = invoice_repo::find_id_by_seller_date_amount(
+                seller,
+                date,
+                invoice.total_amount,
+                non_empty(&invoice.invoice_number),
+            )? {
+                return Ok(Some((id, DuplicateReason::Fuzzy)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::models::invoice::InvoiceType;
+    use crate::models::money::Money;
+
+    #[test]
+    fn test_find_duplicates() {
+        db::init_test_database().unwrap();
+        let number = uuid::Uuid::new_v4().simple().to_string();
+        let seller = format!("测试销售方{}", number);
+
+        let mut existing = Invoice::new(InvoiceType::VatInvoice, Money::from_fen(11300));
+        existing.invoice_code = Some("044001900111".to_string());
+        existing.invoice_number = Some(number.clone());
+        existing.invoice_date = Some("2024-03-01".to_string());
+        existing.seller_name = Some(seller.clone());
+        invoice_repo::insert(&existing).unwrap();
+
+        let mut same_number = Invoice::new(InvoiceType::VatInvoice, Money::from_fen(100));
+        same_number.invoice_code = existing.invoice_code.clone();
+        same_number.invoice_number = existing.invoice_number.clone();
+        assert_eq!(
+            DuplicateDetector::find(&same_number, None).unwrap(),
+            Some((existing.id.clone(), DuplicateReason::CodeNumber))
+        );
+
+        // 号码不同的两张发票不是重复
+        let mut other_number = existing.clone();
+        other_number.invoice_number = Some(format!("{}-2", number));
+        assert_eq!(DuplicateDetector::find(&other_number, None).unwrap(), None);
+
+        let mut fuzzy = existing.clone();
+        fuzzy.invoice_code = None;
+        fuzzy.invoice_number = None;
+        assert_eq!(
+            DuplicateDetector::find(&fuzzy, None).unwrap(),
+            Some((existing.id.clone(), DuplicateReason::Fuzzy))
+        );
+
+        fuzzy.total_amount = Money::from_fen(11400);
+        assert_eq!(DuplicateDetector::find(&fuzzy, None).unwrap(), None);
+    }
+}
@@ -96,14 +96,14 @@ impl ExportService {
 
         // 价税合计
         worksheet
-            .write_number(row, col, invoice.total_amount)
+            .write_number(row, col, invoice.total_amount.to_yuan())
             .map_err(|e| AppError::FileProcess(e.to_string()))?;
         col += 1;
 
         // 不含税金额
         if let Some(amount) = invoice.amount_without_tax {
             worksheet
-                .write_number(row, col, amount)
+                .write_number(row, col, amount.to_yuan())
                 .map_err(|e| AppError::FileProcess(e.to_string()))?;
         }
         col += 1;
@@ -111,7 +111,7 @@ impl ExportService {
         // 税额
         if let Some(tax) = invoice.tax_amount {
             worksheet
-                .write_number(row, col, tax)
+                .write_number(row, col, tax.to_yuan())
                 .map_err(|e| AppError::FileProcess(e.to_string()))?;
         }
         col += 1;
@@ -1,13 +1,39 @@
 use crate::error::AppError;
+use crate::services::preprocess::{ImagePreprocessor, PreprocessOptions};
 use base64::{engine::general_purpose::STANDARD, Engine};
-use image::GenericImageView;
+use image::codecs::jpeg::JpegEncoder;
+use image::{
+    DynamicImage, GenericImageView, GrayImage, ImageBuffer, ImageDecoder, ImageReader, Luma,
+    RgbImage, RgbaImage,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tiff::decoder::{Decoder as TiffDecoder, DecodingResult};
+use tiff::ColorType as TiffColorType;
 
-const MAX_IMAGE_SIZE: usize = 4 * 1024 * 1024; // 4MB
-const SUPPORTED_IMAGE_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "bmp"];
+/// OCR 服务对请求体中文件（base64 编码并 urlencode 后）的大小限制
+const MAX_ENCODED_SIZE: usize = 4 * 1024 * 1024; // 4MB
+/// OCR 服务要求的图片最短边
+const MIN_IMAGE_SIDE: u32 = 15;
+/// OCR 服务允许的图片最长边
+const MAX_IMAGE_SIDE: u32 = 4096;
+/// 压缩时依次尝试的 JPEG 质量
+const JPEG_QUALITIES: [u8; 4] = [90, 80, 70, 60];
+/// 降低质量仍超限时每轮缩小的比例
+const SHRINK_FACTOR: f64 = 0.75;
+const SUPPORTED_IMAGE_EXTENSIONS: [&str; 9] = [
+    "jpg", "jpeg", "png", "bmp", "tif", "tiff", "webp", "heic", "heif",
+];
 const SUPPORTED_PDF_EXTENSION: &str = "pdf";
+const SUPPORTED_OFD_EXTENSION: &str = "ofd";
+/// 判断文件类型时读取的文件头长度
+const SNIFF_LEN: usize = 1024;
+/// 预处理图片生成后至少保留的时间，避免清理掉正在保存的发票引用的图片
+const PROCESSED_MIN_AGE: Duration = Duration::from_secs(3600);
 
 /// 文件类型枚举
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -16,7 +42,14 @@ pub enum FileType {
     Jpeg,
     Png,
     Bmp,
+    /// TIFF（扫描仪常见，可能有多页）
+    Tiff,
+    Webp,
+    /// HEIC/HEIF（手机照片）
+    Heic,
     Pdf,
+    /// OFD 版式文件（数电票）
+    Ofd,
 }
 
 impl FileType {
@@ -25,7 +58,40 @@ impl FileType {
             FileType::Jpeg => "jpeg",
             FileType::Png => "png",
             FileType::Bmp => "bmp",
+            FileType::Tiff => "tiff",
+            FileType::Webp => "webp",
+            FileType::Heic => "heic",
             FileType::Pdf => "pdf",
+            FileType::Ofd => "ofd",
+        }
+    }
+
+    /// MIME 类型
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FileType::Jpeg => "image/jpeg",
+            FileType::Png => "image/png",
+            FileType::Bmp => "image/bmp",
+            FileType::Tiff => "image/tiff",
+            FileType::Webp => "image/webp",
+            FileType::Heic => "image/heic",
+            FileType::Pdf => "application/pdf",
+            FileType::Ofd => "application/ofd",
+        }
+    }
+
+    /// 是否为图片
+    pub fn is_image(&self) -> bool {
+        !matches!(self, FileType::Pdf | FileType::Ofd)
+    }
+
+    /// OCR 服务不接受该格式时，提交前需要转换成的格式
+    pub fn conversion_target(&self) -> Option<FileType> {
+        match self {
+            // 扫描件转 PNG 保持清晰，照片转 JPEG 控制体积
+            FileType::Tiff => Some(FileType::Png),
+            FileType::Webp | FileType::Heic => Some(FileType::Jpeg),
+            _ => None,
         }
     }
 }
@@ -34,9 +100,23 @@ impl FileType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub path: String,
+    /// 按文件内容识别出的类型
     pub file_type: FileType,
+    /// 按文件内容识别出的 MIME 类型
+    pub mime_type: String,
     pub size: u64,
     pub name: String,
+    /// 页数（PDF、多页 TIFF 之外均为 1）
+    pub page_count: u32,
+    /// 提交 OCR 前转换成的格式，无需转换时为 None
+    pub converted_to: Option<FileType>,
+}
+
+impl FileInfo {
+    /// 实际提交给 OCR 服务的文件格式
+    pub fn ocr_file_type(&self) -> &FileType {
+        self.converted_to.as_ref().unwrap_or(&self.file_type)
+    }
 }
 
 pub struct FileService;
@@ -44,37 +124,143 @@ pub struct FileService;
 impl FileService {
     /// 读取图片文件并转换为 base64
     pub fn read_image_as_base64(path: &Path) -> Result<String, AppError> {
+        Self::read_page_as_base64(path, 1)
+    }
+
+    /// 读取文件的指定页（从 1 开始）并转换为 base64
+    ///
+    /// OCR 服务不接受的图片格式会先转换（见 `FileType::conversion_target`）；
+    /// PDF 始终返回整个文件，页码在调用 OCR 时指定。
+    /// 图片超出 OCR 服务的大小或边长限制时会压缩，PDF 超限则直接报错。
+    pub fn read_page_as_base64(path: &Path, page: u32) -> Result<String, AppError> {
         let file_type = Self::validate_file_type(path)?;
         let data = fs::read(path).map_err(|e| {
             AppError::FileProcess(format!("读取文件失败: {}", e))
         })?;
-
-        let processed = if file_type != FileType::Pdf && data.len() > MAX_IMAGE_SIZE {
-            Self::compress_image(&data)?
-        } else {
-            data
+        let data = match file_type.conversion_target() {
+            Some(target) => Self::convert_image(&data, &file_type, page, &target)?,
+            None => data,
         };
 
-        Ok(STANDARD.encode(&processed))
+        if file_type.is_image() {
+            return Self::encode_image_within_limits(&data);
+        }
+
+        let encoded = STANDARD.encode(&data);
+        let size = Self::urlencoded_len(&encoded);
+        if size > MAX_ENCODED_SIZE {
+            return Err(AppError::FileProcess(format!(
+                "文件编码后约 {:.1}MB，超过 OCR 服务 {}MB 的限制，请拆分或压缩后再上传",
+                size as f64 / 1024.0 / 1024.0,
+                MAX_ENCODED_SIZE / 1024 / 1024
+            )));
+        }
+        Ok(encoded)
     }
 
-    /// 验证文件类型
+    /// 验证文件类型：以文件头为准，扩展名是已支持的类型但与内容不符时报错
     pub fn validate_file_type(path: &Path) -> Result<FileType, AppError> {
+        let mut header = Vec::with_capacity(SNIFF_LEN);
+        fs::File::open(path)
+            .and_then(|file| file.take(SNIFF_LEN as u64).read_to_end(&mut header))
+            .map_err(|e| AppError::FileProcess(format!("读取文件失败: {}", e)))?;
+
         let extension = path
             .extension()
             .and_then(|e| e.to_str())
             .map(|e| e.to_lowercase())
-            .ok_or_else(|| AppError::FileProcess("无法获取文件扩展名".to_string()))?;
-
-        match extension.as_str() {
-            "jpg" | "jpeg" => Ok(FileType::Jpeg),
-            "png" => Ok(FileType::Png),
-            "bmp" => Ok(FileType::Bmp),
-            "pdf" => Ok(FileType::Pdf),
-            _ => Err(AppError::FileProcess(format!(
-                "不支持的文件类型: {}，支持: {:?}, {}",
-                extension, SUPPORTED_IMAGE_EXTENSIONS, SUPPORTED_PDF_EXTENSION
+            .unwrap_or_default();
+
+        let detected = Self::detect_file_type(&header)
+            .filter(|file_type| *file_type != FileType::Ofd || Self::is_ofd_package(path))
+            .ok_or_else(|| {
+                AppError::FileProcess(format!(
+                    "不支持的文件类型: {}，支持: {:?}, {}, {}",
+                    if extension.is_empty() {
+                        "未知"
+                    } else {
+                        &extension
+                    },
+                    SUPPORTED_IMAGE_EXTENSIONS,
+                    SUPPORTED_PDF_EXTENSION,
+                    SUPPORTED_OFD_EXTENSION
+                ))
+            })?;
+
+        match Self::file_type_from_extension(&extension) {
+            Some(expected) if expected != detected => Err(AppError::FileProcess(format!(
+                "文件扩展名为 .{}，但内容是 {} 格式，请确认文件是否被改名",
+                extension,
+                detected.as_str().to_uppercase()
             ))),
+            _ => Ok(detected),
+        }
+    }
+
+    /// 按文件头（magic bytes）判断文件类型
+    ///
+    /// zip 包只能初步判断为 OFD，需再用 [`FileService::is_ofd_package`] 确认。
+    pub fn detect_file_type(header: &[u8]) -> Option<FileType> {
+        const HEIC_BRANDS: [&[u8]; 8] = [
+            b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1",
+        ];
+        // BITMAPCOREHEADER、BITMAPINFOHEADER 及其各版本扩展的长度
+        const BMP_DIB_SIZES: [u32; 7] = [12, 40, 52, 56, 64, 108, 124];
+
+        if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(FileType::Jpeg)
+        } else if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(FileType::Png)
+        } else if header.len() >= 18
+            && header.starts_with(b"BM")
+            && BMP_DIB_SIZES.contains(&u32::from_le_bytes([
+                header[14], header[15], header[16], header[17],
+            ]))
+        {
+            Some(FileType::Bmp)
+        } else if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+            Some(FileType::Tiff)
+        } else if header.len() >= 12 && &header[..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+            Some(FileType::Webp)
+        } else if header.len() >= 12
+            && &header[4..8] == b"ftyp"
+            && HEIC_BRANDS.contains(&&header[8..12])
+        {
+            Some(FileType::Heic)
+        } else if header.windows(5).any(|w| w == b"%PDF-") {
+            // 部分生成器会在 %PDF- 之前写入少量字节
+            Some(FileType::Pdf)
+        } else if header.starts_with(b"PK\x03\x04") {
+            // OFD 是 zip 包，zip 是唯一支持的压缩格式
+            Some(FileType::Ofd)
+        } else {
+            None
+        }
+    }
+
+    /// zip 包根目录下是否有 OFD 入口文件 `OFD.xml`
+    pub fn is_ofd_package(path: &Path) -> bool {
+        fs::File::open(path)
+            .ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+            .is_some_and(|archive| {
+                archive
+                    .file_names()
+                    .any(|name| name.trim_start_matches('/').eq_ignore_ascii_case("OFD.xml"))
+            })
+    }
+
+    fn file_type_from_extension(extension: &str) -> Option<FileType> {
+        match extension {
+            "jpg" | "jpeg" => Some(FileType::Jpeg),
+            "png" => Some(FileType::Png),
+            "bmp" => Some(FileType::Bmp),
+            "tif" | "tiff" => Some(FileType::Tiff),
+            "webp" => Some(FileType::Webp),
+            "heic" | "heif" => Some(FileType::Heic),
+            "pdf" => Some(FileType::Pdf),
+            "ofd" => Some(FileType::Ofd),
+            _ => None,
         }
     }
 
@@ -91,46 +277,359 @@ impl FileService {
             .unwrap_or("unknown")
             .to_string();
 
+        let page_count = match file_type {
+            FileType::Pdf | FileType::Tiff => {
+                let data = fs::read(path).map_err(|e| {
+                    AppError::FileProcess(format!("读取文件失败: {}", e))
+                })?;
+                // 无法解析页结构时按单页处理，交由后续解码或 OCR 服务判断
+                if file_type == FileType::Pdf {
+                    Self::pdf_page_count(&data).unwrap_or(1)
+                } else {
+                    Self::tiff_page_count(&data).unwrap_or(1)
+                }
+            }
+            _ => 1,
+        };
+
         Ok(FileInfo {
             path: path.to_string_lossy().to_string(),
-            file_type,
             size: metadata.len(),
             name,
+            page_count,
+            mime_type: file_type.mime_type().to_string(),
+            converted_to: file_type.conversion_target(),
+            file_type,
         })
     }
 
-    /// 压缩图片（如果超过限制大小）
-    fn compress_image(data: &[u8]) -> Result<Vec<u8>, AppError> {
+    /// 获取 PDF 页数
+    pub fn pdf_page_count(data: &[u8]) -> Result<u32, AppError> {
+        let document = lopdf::Document::load_mem(data).map_err(|e| {
+            AppError::FileProcess(format!("解析PDF失败: {}", e))
+        })?;
+        Ok((document.get_pages().len() as u32).max(1))
+    }
+
+    /// 获取 TIFF 页数
+    pub fn tiff_page_count(data: &[u8]) -> Result<u32, AppError> {
+        let mut decoder = TiffDecoder::new(Cursor::new(data))
+            .map_err(|e| AppError::FileProcess(format!("解析TIFF失败: {}", e)))?;
+        let mut count = 1;
+        while decoder.more_images() {
+            decoder
+                .next_image()
+                .map_err(|e| AppError::FileProcess(format!("解析TIFF失败: {}", e)))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// 按选项预处理图片的指定页，处理结果在 `output_dir` 下另存为 JPEG 供核对，返回保存路径
+    pub fn preprocess_page(
+        path: &Path,
+        page: u32,
+        options: &PreprocessOptions,
+        output_dir: &Path,
+    ) -> Result<PathBuf, AppError> {
+        let file_type = Self::validate_file_type(path)?;
+        let data = fs::read(path).map_err(|e| {
+            AppError::FileProcess(format!("读取文件失败: {}", e))
+        })?;
+        let img = Self::decode_page(&data, &file_type, page, options.auto_rotate)?;
+        let img = match ImagePreprocessor::process(img, options) {
+            img @ DynamicImage::ImageLuma8(_) => img,
+            img => DynamicImage::ImageRgb8(img.to_rgb8()),
+        };
+
+        fs::create_dir_all(output_dir)
+            .map_err(|e| AppError::FileProcess(format!("创建目录失败: {}", e)))?;
+        let output = output_dir.join(format!("{}.jpg", uuid::Uuid::new_v4()));
+        img.save_with_format(&output, image::ImageFormat::Jpeg)
+            .map_err(|e| AppError::FileProcess(format!("保存预处理图片失败: {}", e)))?;
+        Ok(output)
+    }
+
+    /// 随发票保存的预处理图片目录
+    pub fn processed_dir() -> Result<PathBuf, AppError> {
+        Ok(crate::db::app_data_dir()?.join("processed"))
+    }
+
+    /// 仅预览、不保存时的预处理图片目录（系统临时目录）
+    pub fn preview_dir() -> PathBuf {
+        std::env::temp_dir().join("tax-recognize-processed")
+    }
+
+    /// 清理没有发票引用的预处理图片，返回清理的数量
+    pub fn collect_processed(referenced: &HashSet<String>) -> Result<u32, AppError> {
+        let dir = Self::processed_dir()?;
+        if !dir.exists() {
+            return Ok(0);
+        }
+        Self::remove_unreferenced(&dir, referenced, PROCESSED_MIN_AGE)
+    }
+
+    /// 清理预览留下的预处理图片，刚生成、可能仍在展示的保留，返回清理的数量
+    pub fn collect_previews() -> Result<u32, AppError> {
+        let dir = Self::preview_dir();
+        if !dir.exists() {
+            return Ok(0);
+        }
+        Self::remove_unreferenced(&dir, &HashSet::new(), PROCESSED_MIN_AGE)
+    }
+
+    /// 删除目录中未被引用、且生成时间超过 `min_age` 的文件
+    fn remove_unreferenced(
+        dir: &Path,
+        referenced: &HashSet<String>,
+        min_age: Duration,
+    ) -> Result<u32, AppError> {
+        let mut removed = 0;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let recent = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_none_or(|age| age < min_age);
+            if recent || !path.is_file() || referenced.contains(path.to_string_lossy().as_ref()) {
+                continue;
+            }
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// 解码图片的指定页，`apply_orientation` 为 true 时按 EXIF 方向摆正
+    fn decode_page(
+        data: &[u8],
+        file_type: &FileType,
+        page: u32,
+        apply_orientation: bool,
+    ) -> Result<DynamicImage, AppError> {
+        let load_err = |e: image::ImageError| AppError::FileProcess(format!("加载图片失败: {}", e));
+        match file_type {
+            FileType::Tiff => Self::decode_tiff_page(data, page),
+            FileType::Heic => Self::decode_heic(data),
+            _ => {
+                let mut decoder = ImageReader::new(Cursor::new(data))
+                    .with_guessed_format()
+                    .map_err(|e| AppError::FileProcess(format!("加载图片失败: {}", e)))?
+                    .into_decoder()
+                    .map_err(load_err)?;
+                let orientation = decoder.orientation().map_err(load_err)?;
+                let mut img = DynamicImage::from_decoder(decoder).map_err(load_err)?;
+                if apply_orientation {
+                    img.apply_orientation(orientation);
+                }
+                Ok(img)
+            }
+        }
+    }
+
+    /// 把图片（多页 TIFF 取指定页）转换为目标格式
+    fn convert_image(
+        data: &[u8],
+        file_type: &FileType,
+        page: u32,
+        target: &FileType,
+    ) -> Result<Vec<u8>, AppError> {
+        let img = Self::decode_page(data, file_type, page, false)?;
+
+        let (img, format) = match target {
+            // JPEG 不支持透明通道
+            FileType::Jpeg => (
+                DynamicImage::ImageRgb8(img.to_rgb8()),
+                image::ImageFormat::Jpeg,
+            ),
+            _ => (img, image::ImageFormat::Png),
+        };
+
+        let mut buffer = Vec::new();
+        img.write_to(&mut Cursor::new(&mut buffer), format)
+            .map_err(|e| AppError::FileProcess(format!("转换图片格式失败: {}", e)))?;
+        Ok(buffer)
+    }
+
+    /// 解码 TIFF 的指定页（从 1 开始）
+    fn decode_tiff_page(data: &[u8], page: u32) -> Result<DynamicImage, AppError> {
+        let tiff_err = |e: tiff::TiffError| AppError::FileProcess(format!("解析TIFF失败: {}", e));
+
+        let mut decoder = TiffDecoder::new(Cursor::new(data)).map_err(tiff_err)?;
+        decoder
+            .seek_to_image(page.saturating_sub(1) as usize)
+            .map_err(tiff_err)?;
+        let (width, height) = decoder.dimensions().map_err(tiff_err)?;
+        let color_type = decoder.colortype().map_err(tiff_err)?;
+
+        let img = match (color_type, decoder.read_image().map_err(tiff_err)?) {
+            // 黑白扫描件每像素 1 位，逐行展开为灰度
+            (TiffColorType::Gray(1), DecodingResult::U8(buf)) => {
+                let row_bytes = width.div_ceil(8) as usize;
+                (buf.len() >= row_bytes * height as usize).then(|| {
+                    DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+                        let byte = buf[y as usize * row_bytes + x as usize / 8];
+                        Luma([((byte >> (7 - x % 8)) & 1) * 255])
+                    }))
+                })
+            }
+            (TiffColorType::Gray(8), DecodingResult::U8(buf)) => {
+                GrayImage::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+            }
+            (TiffColorType::Gray(16), DecodingResult::U16(buf)) => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+            }
+            (TiffColorType::RGB(8), DecodingResult::U8(buf)) => {
+                RgbImage::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+            }
+            (TiffColorType::RGBA(8), DecodingResult::U8(buf)) => {
+                RgbaImage::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+            }
+            _ => None,
+        };
+
+        img.ok_or_else(|| {
+            AppError::FileProcess(format!("不支持的TIFF颜色格式: {:?}", color_type))
+        })
+    }
+
+    /// 解码 HEIC 主图
+    #[cfg(feature = "heic")]
+    fn decode_heic(data: &[u8]) -> Result<DynamicImage, AppError> {
+        use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+        let heif_err = |e: libheif_rs::HeifError| {
+            AppError::FileProcess(format!("解析HEIC失败: {}", e))
+        };
+        let context = HeifContext::read_from_bytes(data).map_err(heif_err)?;
+        let handle = context.primary_image_handle().map_err(heif_err)?;
+        let decoded = LibHeif::new()
+            .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+            .map_err(heif_err)?;
+        let plane = decoded
+            .planes()
+            .interleaved
+            .ok_or_else(|| AppError::FileProcess("解析HEIC失败: 缺少像素数据".to_string()))?;
+
+        // 每行末尾可能有对齐填充
+        let row_bytes = plane.width as usize * 3;
+        let pixels: Vec<u8> = plane
+            .data
+            .chunks(plane.stride)
+            .take(plane.height as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+        RgbImage::from_raw(plane.width, plane.height, pixels)
+            .map(DynamicImage::ImageRgb8)
+            .ok_or_else(|| AppError::FileProcess("解析HEIC失败: 像素数据不完整".to_string()))
+    }
+
+    #[cfg(not(feature = "heic"))]
+    fn decode_heic(_data: &[u8]) -> Result<DynamicImage, AppError> {
+        Err(AppError::FileProcess(
+            "当前版本未启用 HEIC 支持，请先转换为 JPG 后再上传".to_string(),
+        ))
+    }
+
+    /// 识别图片中的二维码，返回每个二维码的文本
+    ///
+    /// 按文件头判断出的类型解码，扩展名与内容不符的文件同样能识别。
+    pub fn decode_qr_codes(path: &Path, file_type: &FileType) -> Vec<String> {
+        let Ok(img) = fs::read(path)
+            .map_err(|e| AppError::FileProcess(format!("读取文件失败: {}", e)))
+            .and_then(|data| Self::decode_page(&data, file_type, 1, false))
+        else {
+            return Vec::new();
+        };
+        let gray = img.to_luma8();
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+            gray.width() as usize,
+            gray.height() as usize,
+            |x, y| gray.get_pixel(x as u32, y as u32)[0],
+        );
+
+        prepared
+            .detect_grids()
+            .iter()
+            .filter_map(|grid| grid.decode().ok())
+            .map(|(_, content)| content)
+            .collect()
+    }
+
+    /// 编码图片，超出大小或边长限制时先调整尺寸，再逐步降低 JPEG 质量和尺寸直到符合要求
+    fn encode_image_within_limits(data: &[u8]) -> Result<String, AppError> {
         let img = image::load_from_memory(data).map_err(|e| {
             AppError::FileProcess(format!("加载图片失败: {}", e))
         })?;
-
         let (width, height) = img.dimensions();
-        let scale = Self::calculate_scale(data.len(), width, height);
 
-        let new_width = (width as f64 * scale) as u32;
-        let new_height = (height as f64 * scale) as u32;
+        let mut scale = Self::side_scale(width, height)?;
+        if scale == 1.0 {
+            let encoded = STANDARD.encode(data);
+            if Self::urlencoded_len(&encoded) <= MAX_ENCODED_SIZE {
+                return Ok(encoded);
+            }
+        }
 
-        let resized = img.resize(
-            new_width,
-            new_height,
-            image::imageops::FilterType::Lanczos3,
-        );
+        loop {
+            let new_width = ((width as f64 * scale).round() as u32).max(1);
+            let new_height = ((height as f64 * scale).round() as u32).max(1);
+            if new_width.min(new_height) < MIN_IMAGE_SIDE {
+                return Err(AppError::FileProcess(
+                    "图片压缩到最小尺寸后仍超过 OCR 服务的大小限制".to_string(),
+                ));
+            }
 
-        let mut buffer = Vec::new();
-        let mut cursor = std::io::Cursor::new(&mut buffer);
+            let resized = if (new_width, new_height) == (width, height) {
+                img.to_rgb8()
+            } else {
+                img.resize_exact(new_width, new_height, image::imageops::FilterType::Lanczos3)
+                    .to_rgb8()
+            };
+            for quality in JPEG_QUALITIES {
+                let mut buffer = Vec::new();
+                resized
+                    .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, quality))
+                    .map_err(|e| AppError::FileProcess(format!("压缩图片失败: {}", e)))?;
+                let encoded = STANDARD.encode(&buffer);
+                if Self::urlencoded_len(&encoded) <= MAX_ENCODED_SIZE {
+                    return Ok(encoded);
+                }
+            }
+            scale *= SHRINK_FACTOR;
+        }
+    }
 
-        resized
-            .write_to(&mut cursor, image::ImageFormat::Jpeg)
-            .map_err(|e| AppError::FileProcess(format!("压缩图片失败: {}", e)))?;
+    /// 使边长落在 OCR 服务要求范围内的缩放比例
+    fn side_scale(width: u32, height: u32) -> Result<f64, AppError> {
+        let (short, long) = (width.min(height) as f64, width.max(height) as f64);
+        let scale = if long > MAX_IMAGE_SIDE as f64 {
+            MAX_IMAGE_SIDE as f64 / long
+        } else if short < MIN_IMAGE_SIDE as f64 {
+            MIN_IMAGE_SIDE as f64 / short.max(1.0)
+        } else {
+            1.0
+        };
 
-        Ok(buffer)
+        let (short, long) = (short * scale, long * scale);
+        if short.round() < MIN_IMAGE_SIDE as f64 || long.round() > MAX_IMAGE_SIDE as f64 {
+            return Err(AppError::FileProcess(format!(
+                "图片尺寸 {}x{} 不符合 OCR 服务要求（边长需在 {}~{} 像素之间）",
+                width, height, MIN_IMAGE_SIDE, MAX_IMAGE_SIDE
+            )));
+        }
+        Ok(scale)
     }
 
-    fn calculate_scale(current_size: usize, _width: u32, _height: u32) -> f64 {
-        let ratio = MAX_IMAGE_SIZE as f64 / current_size as f64;
-        let scale = ratio.sqrt();
-        scale.min(1.0).max(0.1)
+    /// base64 经 urlencode 后的长度（`+`、`/`、`=` 各编码为 3 个字符）
+    fn urlencoded_len(base64: &str) -> usize {
+        let escaped = base64
+            .bytes()
+            .filter(|b| matches!(b, b'+' | b'/' | b'='))
+            .count();
+        base64.len() + escaped * 2
     }
 
     /// 检查文件是否存在
@@ -142,6 +641,147 @@ impl FileService {
     pub fn supported_extensions() -> Vec<&'static str> {
         let mut exts: Vec<&str> = SUPPORTED_IMAGE_EXTENSIONS.to_vec();
         exts.push(SUPPORTED_PDF_EXTENSION);
+        exts.push(SUPPORTED_OFD_EXTENSION);
         exts
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiff::encoder::{colortype::Gray8, TiffEncoder};
+
+    #[test]
+    fn test_remove_unreferenced_processed_files() {
+        let dir = std::env::temp_dir()
+            .join("tax-recognize-tests")
+            .join(format!("processed-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let (kept, orphan) = (dir.join("kept.jpg"), dir.join("orphan.jpg"));
+        fs::write(&kept, b"jpeg").unwrap();
+        fs::write(&orphan, b"jpeg").unwrap();
+        let referenced = HashSet::from([kept.to_string_lossy().to_string()]);
+
+        // 刚生成的图片不清理
+        let removed =
+            FileService::remove_unreferenced(&dir, &referenced, PROCESSED_MIN_AGE).unwrap();
+        assert_eq!(removed, 0);
+
+        let removed = FileService::remove_unreferenced(&dir, &referenced, Duration::ZERO).unwrap();
+        assert_eq!(removed, 1);
+        assert!(kept.exists());
+        assert!(!orphan.exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_multi_page_tiff_converted_per_page() {
+        let mut data = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut data).unwrap();
+        encoder.write_image::<Gray8>(20, 15, &[0; 300]).unwrap();
+        encoder.write_image::<Gray8>(20, 15, &[255; 300]).unwrap();
+
+        let dir = std::env::temp_dir().join("tax-recognize-tests");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("scan-{}.tif", uuid::Uuid::new_v4()));
+        fs::write(&path, data.into_inner()).unwrap();
+
+        let info = FileService::get_file_info(&path).unwrap();
+        assert_eq!(info.file_type, FileType::Tiff);
+        assert_eq!(info.page_count, 2);
+        assert_eq!(info.ocr_file_type(), &FileType::Png);
+
+        let page = STANDARD
+            .decode(FileService::read_page_as_base64(&path, 2).unwrap())
+            .unwrap();
+        assert_eq!(
+            image::guess_format(&page).unwrap(),
+            image::ImageFormat::Png
+        );
+        assert_eq!(
+            image::load_from_memory(&page).unwrap().to_luma8().get_pixel(0, 0),
+            &Luma([255])
+        );
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_file_type_detected_from_content() {
+        let png = crate::test_support::write_test_image("sniff");
+
+        let renamed_bin = png.with_extension("bin");
+        fs::copy(&png, &renamed_bin).unwrap();
+        let info = FileService::get_file_info(&renamed_bin).unwrap();
+        assert_eq!(info.file_type, FileType::Png);
+        assert_eq!(info.mime_type, "image/png");
+
+        let renamed_jpg = png.with_extension("jpg");
+        fs::copy(&png, &renamed_jpg).unwrap();
+        let err = FileService::validate_file_type(&renamed_jpg).unwrap_err();
+        assert!(err.to_string().contains("内容是 PNG 格式"));
+
+        for path in [png, renamed_bin, renamed_jpg] {
+            fs::remove_file(path).ok();
+        }
+    }
+
+    #[test]
+    fn test_zip_and_bmp_need_valid_structure() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let zip_with = |name: &str| {
+            let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"<xml/>").unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+        let dir = std::env::temp_dir().join("tax-recognize-tests");
+        fs::create_dir_all(&dir).unwrap();
+        let ofd = dir.join(format!("invoice-{}.ofd", uuid::Uuid::new_v4()));
+        let zip = dir.join(format!("archive-{}.ofd", uuid::Uuid::new_v4()));
+        fs::write(&ofd, zip_with("OFD.xml")).unwrap();
+        fs::write(&zip, zip_with("readme.xml")).unwrap();
+
+        assert_eq!(
+            FileService::validate_file_type(&ofd).unwrap(),
+            FileType::Ofd
+        );
+        assert!(FileService::validate_file_type(&zip).is_err());
+
+        let mut bmp = Vec::new();
+        DynamicImage::new_luma8(4, 4)
+            .write_to(&mut Cursor::new(&mut bmp), image::ImageFormat::Bmp)
+            .unwrap();
+        assert_eq!(FileService::detect_file_type(&bmp), Some(FileType::Bmp));
+        assert_eq!(FileService::detect_file_type(b"BMW 320i 2024"), None);
+        assert_eq!(FileService::detect_file_type(b"BM"), None);
+
+        for path in [ofd, zip] {
+            fs::remove_file(path).ok();
+        }
+    }
+
+    #[test]
+    fn test_image_resized_into_side_limits() {
+        let dimensions = |width: u32, height: u32| {
+            let mut png = Vec::new();
+            DynamicImage::new_luma8(width, height)
+                .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+                .unwrap();
+            let encoded = FileService::encode_image_within_limits(&png).unwrap();
+            image::load_from_memory(&STANDARD.decode(encoded).unwrap())
+                .unwrap()
+                .dimensions()
+        };
+
+        assert_eq!(dimensions(100, 50), (100, 50));
+        assert_eq!(dimensions(8192, 100), (4096, 50));
+        assert_eq!(dimensions(10, 40), (15, 60));
+        assert!(FileService::side_scale(5000, 2).is_err());
+    }
+}
@@ -1,92 +1,450 @@
-use crate::db::{config_repo, invoice_repo};
+use crate::db::{self, invoice_repo};
 use crate::error::AppError;
-use crate::models::config::config_keys;
+use crate::models::company::{BuyerCheck, BuyerPolicy, RejectedBuyer};
+use crate::models::duplicate::{DuplicateMatch, DuplicatePolicy, DuplicateReason};
 use crate::models::invoice::{Invoice, InvoiceType};
-use crate::services::file::FileService;
-use crate::services::ocr::OcrService;
+use crate::models::invoice_qr::InvoiceQrCode;
+use crate::models::warning::InvoiceWarning;
+use crate::services::attachment::{AttachmentStore, StoredAttachment};
+use crate::services::buyer_check::BuyerChecker;
+use crate::services::duplicate::DuplicateDetector;
+use crate::services::file::{FileInfo, FileService, FileType};
+use crate::services::ocr::{self, BaiduOcrProvider, Classification, OcrInput, OcrProvider};
+use crate::services::ofd::OfdService;
+use crate::services::pdf_text::PdfTextService;
+use crate::services::preprocess::PreprocessOptions;
+use crate::services::validation::InvoiceValidator;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::path::Path;
 
+/// 调用方指定票种时记录的分类器标识
+pub const CLASSIFIER_USER: &str = "user";
+/// 通用文字识别 + 关键字判断的分类器标识
+pub const CLASSIFIER_KEYWORD: &str = "keyword";
+
 /// 识别结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecognizeResult {
     pub file_path: String,
     pub success: bool,
-    pub invoice: Option<Invoice>,
+    /// 识别出的发票（一图多票时有多张）
+    pub invoices: Vec<Invoice>,
+    /// 与已有记录重复的发票
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateMatch>,
+    /// 购买方不是本公司、按配置未保存的发票
+    #[serde(default)]
+    pub rejected_buyers: Vec<RejectedBuyer>,
     pub error: Option<String>,
 }
 
+impl RecognizeResult {
+    /// 保存成功的结果
+    pub fn saved(file_path: &str, saved: SavedInvoices) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            success: true,
+            invoices: saved.invoices,
+            duplicates: saved.duplicates,
+            rejected_buyers: saved.rejected_buyers,
+            error: None,
+        }
+    }
+
+    /// 识别或保存失败的结果
+    pub fn failed(file_path: &str, error: &AppError) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            success: false,
+            invoices: Vec::new(),
+            duplicates: Vec::new(),
+            rejected_buyers: Vec::new(),
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// 保存结果
+#[derive(Debug, Clone, Default)]
+pub struct SavedInvoices {
+    /// 新保存或合并后的发票
+    pub invoices: Vec<Invoice>,
+    pub duplicates: Vec<DuplicateMatch>,
+    pub rejected_buyers: Vec<RejectedBuyer>,
+}
+
 pub struct InvoiceService {
-    ocr_service: OcrService,
+    ocr_provider: Box<dyn OcrProvider>,
+    preprocess: PreprocessOptions,
+    attachments: Option<AttachmentStore>,
+    duplicate_policy: DuplicatePolicy,
+    buyer_checker: BuyerChecker,
+    buyer_policy: BuyerPolicy,
 }
 
 impl InvoiceService {
-    pub fn new() -> Self {
+    /// 使用配置中选择的 OCR 服务商创建
+    pub fn new() -> Result<Self, AppError> {
+        Ok(Self::with_provider(ocr::create_provider()?)
+            .with_attachment_store(AttachmentStore::new()?)
+            .with_duplicate_policy(DuplicateDetector::policy_from_config()?)
+            .with_buyer_checker(BuyerChecker::from_config()?)
+            .with_buyer_policy(BuyerChecker::policy_from_config()?))
+    }
+
+    /// 使用指定的 OCR 服务商创建
+    pub fn with_provider(ocr_provider: Box<dyn OcrProvider>) -> Self {
         Self {
-            ocr_service: OcrService::new(),
+            ocr_provider,
+            preprocess: PreprocessOptions::default(),
+            attachments: None,
+            duplicate_policy: DuplicatePolicy::default(),
+            buyer_checker: BuyerChecker::default(),
+            buyer_policy: BuyerPolicy::default(),
         }
     }
 
-    /// 获取 API 配置
-    fn get_api_config() -> Result<(String, String), AppError> {
-        let api_key = config_repo::get_config(config_keys::BAIDU_OCR_API_KEY)?
-            .ok_or_else(|| AppError::Config("未配置百度OCR API Key".to_string()))?;
-        let secret_key = config_repo::get_config(config_keys::BAIDU_OCR_SECRET_KEY)?
-            .ok_or_else(|| AppError::Config("未配置百度OCR Secret Key".to_string()))?;
-        Ok((api_key, secret_key))
+    /// 核对购买方是否为本公司（默认不核对）
+    pub fn with_buyer_checker(mut self, buyer_checker: BuyerChecker) -> Self {
+        self.buyer_checker = buyer_checker;
+        self
+    }
+
+    /// 设置购买方不是本公司时的处理方式
+    pub fn with_buyer_policy(mut self, buyer_policy: BuyerPolicy) -> Self {
+        self.buyer_policy = buyer_policy;
+        self
+    }
+
+    /// 设置重复发票的处理方式
+    pub fn with_duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self
     }
 
-    /// 识别发票
+    /// 保存发票时把原始文件归档到附件库（默认不归档）
+    pub fn with_attachment_store(mut self, attachments: AttachmentStore) -> Self {
+        self.attachments = Some(attachments);
+        self
+    }
+
+    /// 识别前对图片做预处理（默认不处理）
+    pub fn with_preprocess(mut self, preprocess: PreprocessOptions) -> Self {
+        self.preprocess = preprocess;
+        self
+    }
+
+    /// 识别发票（PDF 只识别第一页），未指定票种时自动分类
+    ///
+    /// 仅用于预览，预处理图片写入临时目录。
     pub async fn recognize_invoice(
         &self,
         file_path: &str,
         invoice_type: Option<InvoiceType>,
     ) -> Result<Invoice, AppError> {
+        let mut invoice = self
+            .recognize_pages(file_path, invoice_type, false, true)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::Ocr("未识别到票据".to_string()))?;
+        self.check(&mut invoice);
+        Ok(invoice)
+    }
+
+    /// 识别文件中的全部发票：PDF 逐页识别，`split_multiple` 为 true 时每页按一图多票拆分
+    pub async fn recognize_file(
+        &self,
+        file_path: &str,
+        invoice_type: Option<InvoiceType>,
+        split_multiple: bool,
+    ) -> Result<Vec<Invoice>, AppError> {
+        let mut invoices = self
+            .recognize_pages(file_path, invoice_type, split_multiple, false)
+            .await?;
+        invoices.iter_mut().for_each(|invoice| self.check(invoice));
+        Ok(invoices)
+    }
+
+    /// 校验金额、税号并核对购买方
+    fn check(&self, invoice: &mut Invoice) {
+        InvoiceValidator::apply(invoice);
+        self.buyer_checker.apply(invoice);
+    }
+
+    async fn recognize_pages(
+        &self,
+        file_path: &str,
+        invoice_type: Option<InvoiceType>,
+        split_multiple: bool,
+        preview: bool,
+    ) -> Result<Vec<Invoice>, AppError> {
         let path = Path::new(file_path);
         let file_info = FileService::get_file_info(path)?;
-        let image_base64 = FileService::read_image_as_base64(path)?;
+        // OFD 自带结构化发票数据，本地解析即可，无需调用 OCR
+        if file_info.file_type == FileType::Ofd {
+            return Ok(vec![OfdService::parse_file(path)?]);
+        }
+        // 增值税电子发票 PDF 优先读取文字层
+        let use_text_layer = file_info.file_type == FileType::Pdf
+            && !split_multiple
+            && invoice_type.as_ref().is_none_or(InvoiceType::is_vat);
+        let page_texts = if use_text_layer {
+            PdfTextService::extract_pages(path)
+        } else {
+            Vec::new()
+        };
 
-        let (api_key, secret_key) = Self::get_api_config()?;
-        let token = self
-            .ocr_service
-            .get_access_token(&api_key, &secret_key)
-            .await?;
+        // 发票二维码中的代码、号码、金额等比 OCR 结果更可靠
+        let qr_code = if file_info.file_type.is_image()
+            && file_info.page_count == 1
+            && !split_multiple
+        {
+            FileService::decode_qr_codes(path, &file_info.file_type)
+                .iter()
+                .find_map(|content| InvoiceQrCode::parse(content))
+        } else {
+            None
+        };
+
+        // 预览只识别第一页，预处理图片不随发票保存，之前预览留下的顺带清理
+        let mut pages = Self::pages(&file_info);
+        let processed_dir = if preview {
+            pages.truncate(1);
+            FileService::collect_previews().ok();
+            FileService::preview_dir()
+        } else {
+            FileService::processed_dir()?
+        };
 
-        let inv_type = invoice_type.unwrap_or(InvoiceType::VatCommonInvoice);
-        let invoice = self
-            .recognize_by_type(&token, &image_base64, inv_type, &file_info)
+        let mut authenticated = false;
+        // PDF 整份提交并指定页码，其余文件逐页读取
+        let mut pdf_base64: Option<String> = None;
+        let mut invoices = Vec::new();
+        for page in pages {
+            let local = page
+                .and_then(|page| page_texts.get(page as usize - 1))
+                .and_then(|text| PdfTextService::parse_invoice(text));
+            if let Some(mut invoice) = local {
+                invoice.original_file_path = Some(file_info.path.clone());
+                invoice.page_number = page;
+                invoices.push(invoice);
+                continue;
+            }
+
+            if !authenticated {
+                self.ocr_provider.authenticate().await?;
+                authenticated = true;
+            }
+            // 文字层能解析的页面无需上传，PDF 在第一次需要 OCR 时才编码（超出大小限制时报错）
+            if file_info.file_type == FileType::Pdf && pdf_base64.is_none() {
+                pdf_base64 = Some(FileService::read_image_as_base64(path)?);
+            }
+            let mut processed_path = None;
+            let file_base64 = match &pdf_base64 {
+                Some(pdf_base64) => Cow::Borrowed(pdf_base64.as_str()),
+                None if self.preprocess.is_enabled() => {
+                    let processed = FileService::preprocess_page(
+                        path,
+                        page.unwrap_or(1),
+                        &self.preprocess,
+                        &processed_dir,
+                    )?;
+                    let base64 = FileService::read_image_as_base64(&processed)?;
+                    processed_path = Some(processed.to_string_lossy().to_string());
+                    Cow::Owned(base64)
+                }
+                None => Cow::Owned(FileService::read_page_as_base64(path, page.unwrap_or(1))?),
+            };
+            let input = OcrInput {
+                file_base64: &file_base64,
+                // 预处理后的图片统一保存为 JPEG
+                file_type: if processed_path.is_some() {
+                    &FileType::Jpeg
+                } else {
+                    file_info.ocr_file_type()
+                },
+                page,
+            };
+            let result = if split_multiple {
+                self.recognize_multiple(input, &file_info).await
+            } else {
+                self.recognize_page(input, invoice_type.clone(), &file_info, qr_code.as_ref())
+                    .await
+                    .map(|invoice| vec![invoice])
+            };
+
+            let page_invoices = match (result, page) {
+                (Ok(page_invoices), _) => page_invoices,
+                (Err(e), Some(page)) if file_info.page_count > 1 => {
+                    return Err(AppError::Ocr(format!("第{}页识别失败: {}", page, e)));
+                }
+                (Err(e), _) => return Err(e),
+            };
+            invoices.extend(page_invoices.into_iter().map(|mut invoice| {
+                invoice.page_number = page;
+                invoice.processed_file_path = processed_path.clone();
+                invoice
+            }));
+        }
+
+        Ok(invoices)
+    }
+
+    /// 需要识别的页码，单页图片只有一个 `None`
+    fn pages(file_info: &FileInfo) -> Vec<Option<u32>> {
+        if file_info.file_type == FileType::Pdf || file_info.page_count > 1 {
+            (1..=file_info.page_count.max(1)).map(Some).collect()
+        } else {
+            vec![None]
+        }
+    }
+
+    /// 识别单页中的一张发票，未指定票种时自动分类
+    async fn recognize_page(
+        &self,
+        input: OcrInput<'_>,
+        invoice_type: Option<InvoiceType>,
+        file_info: &FileInfo,
+        qr_code: Option<&InvoiceQrCode>,
+    ) -> Result<Invoice, AppError> {
+        let (classification, fallback) = match invoice_type {
+            Some(invoice_type) => (
+                Classification {
+                    invoice_type,
+                    classifier: CLASSIFIER_USER.to_string(),
+                },
+                None,
+            ),
+            None => self.classify(input).await?,
+        };
+
+        let mut invoice = self
+            .recognize_by_type(input, classification.invoice_type, file_info, qr_code)
             .await?;
+        invoice.classified_by = Some(classification.classifier);
+        if let Some(warning) = fallback {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
 
         Ok(invoice)
     }
 
+    /// 自动判断票种：优先使用服务商的分类接口，不可用时按通用识别文字的关键字判断
+    ///
+    /// 鉴权和配置错误直接返回；其余错误改用关键字判断，并返回记录失败原因的提示。
+    async fn classify(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<(Classification, Option<InvoiceWarning>), AppError> {
+        let fallback = match self.ocr_provider.classify(input).await {
+            Ok(Some(classification)) => return Ok((classification, None)),
+            Ok(None) => None,
+            Err(e @ (AppError::Auth(_) | AppError::Config(_))) => return Err(e),
+            Err(e) => Some(InvoiceWarning::ClassifyFallback {
+                error: e.to_string(),
+            }),
+        };
+
+        let response = self.ocr_provider.recognize_generic(input).await?;
+        let classification = Classification {
+            invoice_type: response.classify(),
+            classifier: CLASSIFIER_KEYWORD.to_string(),
+        };
+        Ok((classification, fallback))
+    }
+
     async fn recognize_by_type(
         &self,
-        token: &str,
-        image_base64: &str,
+        input: OcrInput<'_>,
         invoice_type: InvoiceType,
-        file_info: &crate::services::file::FileInfo,
+        file_info: &FileInfo,
+        qr_code: Option<&InvoiceQrCode>,
     ) -> Result<Invoice, AppError> {
         match invoice_type {
             InvoiceType::VatInvoice
             | InvoiceType::VatCommonInvoice
             | InvoiceType::VatElectronicInvoice
-            | InvoiceType::VatRollInvoice => {
-                self.recognize_vat(token, image_base64, file_info).await
-            }
-            _ => self.recognize_generic(token, image_base64, file_info).await,
+            | InvoiceType::VatRollInvoice => self.recognize_vat(input, file_info, qr_code).await,
+            InvoiceType::TrainTicket => self.recognize_train(input, file_info).await,
+            InvoiceType::TaxiTicket => self.recognize_taxi(input, file_info).await,
+            InvoiceType::FlightItinerary => self.recognize_flight(input, file_info).await,
+            _ => self.recognize_generic(input, invoice_type, file_info).await,
         }
     }
 
     async fn recognize_vat(
         &self,
-        token: &str,
-        image_base64: &str,
-        file_info: &crate::services::file::FileInfo,
+        input: OcrInput<'_>,
+        file_info: &FileInfo,
+        qr_code: Option<&InvoiceQrCode>,
+    ) -> Result<Invoice, AppError> {
+        let response = self
+            .ocr_provider
+            .recognize_vat_invoice(input)
+            .await?;
+
+        let raw_json = serde_json::to_string(&response).unwrap_or_default();
+        let invoice = response.to_invoice(
+            Some(&file_info.path),
+            Some(file_info.file_type.as_str()),
+            &raw_json,
+            qr_code,
+        );
+
+        Ok(invoice)
+    }
+
+    async fn recognize_train(
+        &self,
+        input: OcrInput<'_>,
+        file_info: &FileInfo,
     ) -> Result<Invoice, AppError> {
         let response = self
-            .ocr_service
-            .recognize_vat_invoice(token, image_base64, &file_info.file_type)
+            .ocr_provider
+            .recognize_train_ticket(input)
+            .await?;
+
+        let raw_json = serde_json::to_string(&response).unwrap_or_default();
+        let invoice = response.to_invoice(
+            Some(&file_info.path),
+            Some(file_info.file_type.as_str()),
+            &raw_json,
+        );
+
+        Ok(invoice)
+    }
+
+    async fn recognize_taxi(
+        &self,
+        input: OcrInput<'_>,
+        file_info: &FileInfo,
+    ) -> Result<Invoice, AppError> {
+        let response = self
+            .ocr_provider
+            .recognize_taxi_receipt(input)
+            .await?;
+
+        let raw_json = serde_json::to_string(&response).unwrap_or_default();
+        let invoice = response.to_invoice(
+            Some(&file_info.path),
+            Some(file_info.file_type.as_str()),
+            &raw_json,
+        );
+
+        Ok(invoice)
+    }
+
+    async fn recognize_flight(
+        &self,
+        input: OcrInput<'_>,
+        file_info: &FileInfo,
+    ) -> Result<Invoice, AppError> {
+        let response = self
+            .ocr_provider
+            .recognize_air_ticket(input)
             .await?;
 
         let raw_json = serde_json::to_string(&response).unwrap_or_default();
@@ -101,33 +459,193 @@ impl InvoiceService {
 
     async fn recognize_generic(
         &self,
-        token: &str,
-        image_base64: &str,
-        file_info: &crate::services::file::FileInfo,
+        input: OcrInput<'_>,
+        requested_type: InvoiceType,
+        file_info: &FileInfo,
     ) -> Result<Invoice, AppError> {
         let response = self
-            .ocr_service
-            .recognize_invoice(token, image_base64, &file_info.file_type)
+            .ocr_provider
+            .recognize_generic(input)
             .await?;
 
         let raw_json = serde_json::to_string(&response).unwrap_or_default();
-        let mut invoice = Invoice::new(InvoiceType::Other, 0.0);
-        invoice.original_file_path = Some(file_info.path.clone());
-        invoice.file_type = Some(file_info.file_type.as_str().to_string());
-        invoice.ocr_raw_response = Some(raw_json);
+        let mut invoice = response.to_invoice(
+            Some(&file_info.path),
+            Some(file_info.file_type.as_str()),
+            &raw_json,
+        );
+
+        // 票面未能判断票种时，沿用调用方指定的类型
+        if invoice.invoice_type == InvoiceType::Other {
+            invoice.invoice_type = requested_type;
+        }
 
         Ok(invoice)
     }
 
-    /// 识别并保存发票
+    /// 识别单页中的多张票据，每张票据对应一条发票记录
+    async fn recognize_multiple(
+        &self,
+        input: OcrInput<'_>,
+        file_info: &FileInfo,
+    ) -> Result<Vec<Invoice>, AppError> {
+        let response = self.ocr_provider.recognize_multiple(input).await?;
+        if response.words_result.is_empty() {
+            return Err(AppError::Ocr("未识别到票据".to_string()));
+        }
+
+        let classifier = self.ocr_provider.multiple_invoice_classifier();
+        let invoices = response
+            .words_result
+            .iter()
+            .map(|item| {
+                let mut invoice = item.to_invoice(
+                    Some(&file_info.path),
+                    Some(file_info.file_type.as_str()),
+                );
+                invoice.classified_by = Some(classifier.clone());
+                invoice
+            })
+            .collect();
+
+        Ok(invoices)
+    }
+
+    /// 识别并保存文件中的全部发票，`split_multiple` 为 true 时按一图多票拆分
+    ///
+    /// 与已有记录重复的发票按 `duplicate_policy` 处理，并在结果中列出。
     pub async fn recognize_and_save(
         &self,
         file_path: &str,
         invoice_type: Option<InvoiceType>,
-    ) -> Result<Invoice, AppError> {
-        let invoice = self.recognize_invoice(file_path, invoice_type).await?;
-        invoice_repo::insert(&invoice)?;
-        Ok(invoice)
+        split_multiple: bool,
+    ) -> Result<SavedInvoices, AppError> {
+        let invoices = self
+            .recognize_file(file_path, invoice_type, split_multiple)
+            .await?;
+        let path = Path::new(file_path);
+        let file_hash = AttachmentStore::hash_file(path)?;
+
+        let mut saved = SavedInvoices::default();
+        let (invoices, rejected): (Vec<_>, Vec<_>) = invoices.into_iter().partition(|invoice| {
+            self.buyer_policy == BuyerPolicy::Warn
+                || invoice.buyer_check != Some(BuyerCheck::Mismatch)
+        });
+        saved.rejected_buyers = rejected
+            .into_iter()
+            .map(|invoice| RejectedBuyer {
+                invoice_number: invoice.invoice_number,
+                buyer_name: invoice.buyer_name,
+                buyer_tax_number: invoice.buyer_tax_number,
+            })
+            .collect();
+
+        // 先全部与已有记录比对，避免同一文件中的多张发票互相判重
+        let checked = invoices
+            .into_iter()
+            .map(|invoice| {
+                let duplicate = DuplicateDetector::find(&invoice, Some(&file_hash))?;
+                Ok((invoice, duplicate))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        // 同一文件的发票一起保存，任一条失败时全部回滚，并删除本次新归档的附件文件
+        db::with_transaction(|conn| {
+            let mut stored = None;
+            let result = self.save_checked(conn, path, checked, &mut saved, &mut stored);
+            if let (Err(_), Some(attachments), Some(stored)) = (&result, &self.attachments, &stored)
+            {
+                attachments.discard(stored);
+            }
+            result
+        })?;
+        Ok(saved)
+    }
+
+    /// 在事务中保存已比对过重复的发票
+    fn save_checked(
+        &self,
+        conn: &Connection,
+        path: &Path,
+        checked: Vec<(Invoice, Option<(String, DuplicateReason)>)>,
+        saved: &mut SavedInvoices,
+        stored: &mut Option<StoredAttachment>,
+    ) -> Result<(), AppError> {
+        for (mut invoice, duplicate) in checked {
+            let (existing_id, reason) = match duplicate {
+                Some(duplicate) => duplicate,
+                None => {
+                    invoice.attachment_hash = self.attachment_hash(conn, path, stored)?;
+                    match invoice_repo::insert_in(conn, &invoice) {
+                        Ok(()) => {
+                            saved.invoices.push(invoice);
+                            continue;
+                        }
+                        // 同一文件中号码相同的发票比对时尚未入库，由唯一索引拦截后同样按策略处理
+                        Err(AppError::Duplicate { existing_id }) => {
+                            (existing_id, DuplicateReason::CodeNumber)
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            };
+
+            saved.duplicates.push(DuplicateMatch {
+                existing_id: existing_id.clone(),
+                reason,
+                policy: self.duplicate_policy,
+                invoice_number: invoice.invoice_number.clone(),
+            });
+            match self.duplicate_policy {
+                DuplicatePolicy::Reject => {}
+                DuplicatePolicy::Warn => {
+                    invoice.duplicate_of = Some(existing_id.clone());
+                    InvoiceWarning::append_to(
+                        &mut invoice.warnings,
+                        InvoiceWarning::Duplicate {
+                            existing_id,
+                            reason,
+                        },
+                    );
+                    invoice.attachment_hash = self.attachment_hash(conn, path, stored)?;
+                    invoice_repo::insert_in(conn, &invoice)?;
+                    saved.invoices.push(invoice);
+                }
+                DuplicatePolicy::Merge => {
+                    match invoice_repo::find_by_id_in(conn, &existing_id)? {
+                        Some(mut existing) => {
+                            existing.fill_missing_from(&invoice);
+                            self.check(&mut existing);
+                            invoice_repo::update_in(conn, &existing)?;
+                            saved.invoices.push(existing);
+                        }
+                        // 比对后已有记录被删除，按新发票保存
+                        None => {
+                            invoice.attachment_hash = self.attachment_hash(conn, path, stored)?;
+                            invoice_repo::insert_in(conn, &invoice)?;
+                            saved.invoices.push(invoice);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 需要入库新发票时才归档原始文件，同一文件只归档一次
+    fn attachment_hash(
+        &self,
+        conn: &Connection,
+        path: &Path,
+        stored: &mut Option<StoredAttachment>,
+    ) -> Result<Option<String>, AppError> {
+        let Some(attachments) = &self.attachments else {
+            return Ok(None);
+        };
+        if stored.is_none() {
+            *stored = Some(attachments.store_in(conn, path)?);
+        }
+        Ok(stored.as_ref().map(|s| s.attachment.hash.clone()))
     }
 
     /// 批量识别
@@ -135,26 +653,17 @@ impl InvoiceService {
         &self,
         file_paths: &[String],
         invoice_type: Option<InvoiceType>,
+        split_multiple: bool,
     ) -> Vec<RecognizeResult> {
         let mut results = Vec::with_capacity(file_paths.len());
 
         for file_path in file_paths {
             let result = match self
-                .recognize_and_save(file_path, invoice_type.clone())
+                .recognize_and_save(file_path, invoice_type.clone(), split_multiple)
                 .await
             {
-                Ok(invoice) => RecognizeResult {
-                    file_path: file_path.clone(),
-                    success: true,
-                    invoice: Some(invoice),
-                    error: None,
-                },
-                Err(e) => RecognizeResult {
-                    file_path: file_path.clone(),
-                    success: false,
-                    invoice: None,
-                    error: Some(e.to_string()),
-                },
+                Ok(saved) => RecognizeResult::saved(file_path, saved),
+                Err(e) => RecognizeResult::failed(file_path, &e),
             };
             results.push(result);
         }
@@ -162,18 +671,341 @@ impl InvoiceService {
         results
     }
 
-    /// 测试 OCR 连接
-    pub async fn test_connection(
-        &self,
-        api_key: &str,
-        secret_key: &str,
-    ) -> Result<bool, AppError> {
-        self.ocr_service.test_connection(api_key, secret_key).await
+    /// 测试 OCR 连接（使用百度凭证）
+    pub async fn test_connection(api_key: &str, secret_key: &str) -> Result<bool, AppError> {
+        BaiduOcrProvider::with_credentials(api_key, secret_key)
+            .health_check()
+            .await
     }
 }
 
-impl Default for InvoiceService {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::config_repo;
+    use crate::models::company::CompanyEntity;
+    use crate::models::config::config_keys;
+    use crate::models::money::Money;
+    use crate::test_support::{self, MockBaiduServer};
+
+    #[tokio::test]
+    async fn test_recognize_and_save_with_mock_server() {
+        db::init_test_database().unwrap();
+        let mut server = MockBaiduServer::start().await;
+        server
+            .replay(
+                "/rest/2.0/ocr/v1/vat_invoice",
+                test_support::BAIDU_VAT_INVOICE_JSON,
+            )
+            .await;
+
+        config_repo::set_config(config_keys::BAIDU_OCR_BASE_URL, &server.url(), None).unwrap();
+        config_repo::set_config(config_keys::BAIDU_OCR_API_KEY, "test-api-key", None).unwrap();
+        config_repo::set_config(config_keys::BAIDU_OCR_SECRET_KEY, "test-secret", None).unwrap();
+        config_repo::delete_config(config_keys::BAIDU_OCR_ACCESS_TOKEN).unwrap();
+
+        let image_path = test_support::write_test_image("vat");
+        let store_dir = image_path.with_extension("store");
+        let service = InvoiceService::new()
+            .unwrap()
+            .with_attachment_store(AttachmentStore::with_root(&store_dir))
+            .with_duplicate_policy(DuplicatePolicy::Warn);
+        let saved = service
+            .recognize_and_save(
+                &image_path.to_string_lossy(),
+                Some(InvoiceType::VatInvoice),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(saved.invoices.len(), 1);
+        assert!(saved.duplicates.is_empty());
+        let invoice = &saved.invoices[0];
+        server.assert_all_called();
+
+        assert_eq!(invoice.invoice_type, InvoiceType::VatInvoice);
+        assert_eq!(invoice.invoice_number.as_deref(), Some("05968312"));
+        assert_eq!(invoice.total_amount, Money::from_fen(113000));
+        assert_eq!(invoice.tax_amount, Some(Money::from_fen(13000)));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-01-15"));
+
+        let saved = invoice_repo::find_by_id(&invoice.id).unwrap().unwrap();
+        assert_eq!(saved.seller_name.as_deref(), Some("杭州示例办公用品有限公司"));
+
+        // 再次导入同一张发票：保存并提示重复
+        let again = service
+            .recognize_and_save(
+                &image_path.to_string_lossy(),
+                Some(InvoiceType::VatInvoice),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(again.duplicates.len(), 1);
+        assert_eq!(again.duplicates[0].existing_id, invoice.id);
+        assert!(again.invoices[0]
+            .warnings
+            .as_deref()
+            .is_some_and(|w| w.contains("duplicate")));
+
+        std::fs::remove_file(image_path).ok();
+        std::fs::remove_dir_all(store_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_classify_falls_back_to_keywords() {
+        db::init_test_database().unwrap();
+        let input = OcrInput {
+            file_base64: "",
+            file_type: &FileType::Png,
+            page: None,
+        };
+        let service = |server: &MockBaiduServer| {
+            InvoiceService::with_provider(Box::new(
+                BaiduOcrProvider::with_credentials("test-api-key", "test-secret")
+                    .with_base_url(server.url()),
+            ))
+        };
+
+        // 分类接口超出配额：改用通用识别 + 关键字判断
+        let mut server = MockBaiduServer::start().await;
+        server
+            .replay(
+                "/rest/2.0/ocr/v1/multiple_invoice",
+                r#"{"error_code": 17, "error_msg": "Open api daily request limit reached"}"#,
+            )
+            .await;
+        server
+            .replay(
+                "/rest/2.0/ocr/v1/invoice",
+                r#"{"words_result": {"InvoiceType": "定额发票", "AmountInFiguers": "10.00"}}"#,
+            )
+            .await;
+        let (classification, fallback) = service(&server).classify(input).await.unwrap();
+        assert_eq!(classification.invoice_type, InvoiceType::QuotaInvoice);
+        assert_eq!(classification.classifier, CLASSIFIER_KEYWORD);
+        assert!(matches!(
+            fallback,
+            Some(InvoiceWarning::ClassifyFallback { error }) if error.contains("request limit")
+        ));
+        server.assert_all_called();
+
+        // token 失效不回退，直接返回错误
+        let mut server = MockBaiduServer::start().await;
+        server
+            .replay(
+                "/rest/2.0/ocr/v1/multiple_invoice",
+                r#"{"error_code": 110, "error_msg": "Access token invalid or no longer valid"}"#,
+            )
+            .await;
+        let result = service(&server).classify(input).await;
+        assert!(matches!(result, Err(AppError::Auth(_))));
+    }
+
+    #[tokio::test]
+    async fn test_recognize_multi_page_pdf() {
+        db::init_test_database().unwrap();
+        let mut server = MockBaiduServer::start().await;
+        // 每页返回不同的发票号码，核对请求中的页码与结果一一对应
+        for page in ["1", "2", "3"] {
+            server
+                .replay_param(
+                    "/rest/2.0/ocr/v1/vat_invoice",
+                    "pdf_file_num",
+                    page,
+                    &test_support::BAIDU_VAT_INVOICE_JSON
+                        .replace("05968312", &format!("0596831{}", page)),
+                )
+                .await;
+        }
+
+        let pdf_path = test_support::write_test_pdf("merged", 3);
+        assert_eq!(FileService::get_file_info(&pdf_path).unwrap().page_count, 3);
+
+        let service = InvoiceService::with_provider(Box::new(
+            BaiduOcrProvider::with_credentials("test-api-key", "test-secret")
+                .with_base_url(server.url()),
+        ));
+        let invoices = service
+            .recognize_file(
+                &pdf_path.to_string_lossy(),
+                Some(InvoiceType::VatInvoice),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let pages: Vec<_> = invoices
+            .iter()
+            .map(|i| (i.page_number, i.invoice_number.as_deref()))
+            .collect();
+        assert_eq!(
+            pages,
+            vec![
+                (Some(1), Some("05968311")),
+                (Some(2), Some("05968312")),
+                (Some(3), Some("05968313")),
+            ]
+        );
+        server.assert_all_called();
+
+        std::fs::remove_file(pdf_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_reimport_split_page_matches_each_ticket() {
+        db::init_test_database().unwrap();
+        let mut server = MockBaiduServer::start().await;
+        server
+            .replay(
+                "/rest/2.0/ocr/v1/multiple_invoice",
+                r#"{"words_result_num": 2, "log_id": 1, "words_result": [
+                    {"type": "quota_invoice", "probability": 0.99,
+                     "left": 10, "top": 10, "width": 200, "height": 100,
+                     "result": {"AmountInFiguers": [{"word": "10.00"}]}},
+                    {"type": "quota_invoice", "probability": 0.98,
+                     "left": 10, "top": 200, "width": 200, "height": 100,
+                     "result": {"AmountInFiguers": [{"word": "20.00"}]}}
+                ]}"#,
+            )
+            .await;
+
+        let image_path = test_support::write_test_image("split-reimport");
+        let store_dir = image_path.with_extension("store");
+        let service = InvoiceService::with_provider(Box::new(
+            BaiduOcrProvider::with_credentials("test-api-key", "test-secret")
+                .with_base_url(server.url()),
+        ))
+        .with_attachment_store(AttachmentStore::with_root(&store_dir))
+        .with_duplicate_policy(DuplicatePolicy::Reject);
+        let path = image_path.to_string_lossy();
+
+        let first = service.recognize_and_save(&path, None, true).await.unwrap();
+        assert_eq!(first.invoices.len(), 2);
+
+        // 再次导入：每张票据各自对应原来的记录
+        let again = service.recognize_and_save(&path, None, true).await.unwrap();
+        assert!(again.invoices.is_empty());
+        let matched: Vec<_> = again
+            .duplicates
+            .iter()
+            .map(|d| (d.existing_id.as_str(), d.reason))
+            .collect();
+        assert_eq!(
+            matched,
+            vec![
+                (first.invoices[0].id.as_str(), DuplicateReason::FileHash),
+                (first.invoices[1].id.as_str(), DuplicateReason::FileHash),
+            ]
+        );
+
+        std::fs::remove_file(image_path).ok();
+        std::fs::remove_dir_all(store_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_save_rejects_other_buyers() {
+        db::init_test_database().unwrap();
+        let mut server = MockBaiduServer::start().await;
+        let json = test_support::BAIDU_VAT_INVOICE_JSON.replace("杭州示例办公用品", "杭州示例购方");
+        server
+            .replay_param(
+                "/rest/2.0/ocr/v1/vat_invoice",
+                "pdf_file_num",
+                "1",
+                &json.replace("05968312", "05960024"),
+            )
+            .await;
+        server
+            .replay_param(
+                "/rest/2.0/ocr/v1/vat_invoice",
+                "pdf_file_num",
+                "2",
+                &json
+                    .replace("05968312", "05960025")
+                    .replace("杭州示例科技有限公司", "宁波其他贸易有限公司")
+                    .replace("91330106MA27XXXX0Q", "91330201MA28YYYY3X"),
+            )
+            .await;
+
+        let pdf_path = test_support::write_test_pdf("buyer-reject", 2);
+        let service = InvoiceService::with_provider(Box::new(
+            BaiduOcrProvider::with_credentials("test-api-key", "test-secret")
+                .with_base_url(server.url()),
+        ))
+        .with_buyer_checker(BuyerChecker::new(vec![CompanyEntity {
+            name: "杭州示例科技有限公司".to_string(),
+            tax_number: "91330106MA27XXXX0Q".to_string(),
+        }]))
+        .with_buyer_policy(BuyerPolicy::Reject);
+        let saved = service
+            .recognize_and_save(
+                &pdf_path.to_string_lossy(),
+                Some(InvoiceType::VatInvoice),
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(saved.invoices.len(), 1);
+        assert_eq!(saved.invoices[0].buyer_check, Some(BuyerCheck::Matched));
+        assert_eq!(saved.rejected_buyers.len(), 1);
+        let rejected = &saved.rejected_buyers[0];
+        assert_eq!(rejected.invoice_number.as_deref(), Some("05960025"));
+        assert_eq!(rejected.buyer_name.as_deref(), Some("宁波其他贸易有限公司"));
+        assert_eq!(
+            rejected.buyer_tax_number.as_deref(),
+            Some("91330201MA28YYYY3X")
+        );
+        let code = saved.invoices[0].invoice_code.as_deref();
+        assert!(invoice_repo::find_id_by_code_number(code, "05960025")
+            .unwrap()
+            .is_none());
+
+        std::fs::remove_file(pdf_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_save_same_number_in_one_file_follows_policy() {
+        db::init_test_database().unwrap();
+        let mut server = MockBaiduServer::start().await;
+        // 两页是同一张发票，比对时都还未入库，第二张由唯一索引拦截
+        server
+            .replay(
+                "/rest/2.0/ocr/v1/vat_invoice",
+                &test_support::BAIDU_VAT_INVOICE_JSON
+                    .replace("05968312", "05960019")
+                    .replace("杭州示例办公用品", "杭州示例同号"),
+            )
+            .await;
+
+        let pdf_path = test_support::write_test_pdf("same-number", 2);
+        let service = InvoiceService::with_provider(Box::new(
+            BaiduOcrProvider::with_credentials("test-api-key", "test-secret")
+                .with_base_url(server.url()),
+        ))
+        .with_duplicate_policy(DuplicatePolicy::Warn);
+        let saved = service
+            .recognize_and_save(
+                &pdf_path.to_string_lossy(),
+                Some(InvoiceType::VatInvoice),
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(saved.invoices.len(), 2);
+        assert_eq!(saved.duplicates.len(), 1);
+        assert_eq!(saved.duplicates[0].policy, DuplicatePolicy::Warn);
+        assert_eq!(saved.duplicates[0].existing_id, saved.invoices[0].id);
+        let second = invoice_repo::find_by_id(&saved.invoices[1].id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            second.duplicate_of.as_deref(),
+            Some(saved.invoices[0].id.as_str())
+        );
+
+        std::fs::remove_file(pdf_path).ok();
     }
 }
@@ -1,4 +1,12 @@
+pub mod attachment;
+pub mod buyer_check;
+pub mod duplicate;
 pub mod export;
 pub mod file;
 pub mod invoice;
 pub mod ocr;
+pub mod ofd;
+pub mod pdf_text;
+pub mod preprocess;
+pub mod tax_id;
+pub mod validation;
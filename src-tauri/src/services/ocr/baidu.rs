@@ -0,0 +1,318 @@
+use crate::db::config_repo;
+use crate::error::AppError;
+use crate::models::config::config_keys;
+use crate::models::invoice::InvoiceType;
+use crate::models::ocr_response::{
+    AirTicketResponse, BaiduErrorResponse, BaiduTokenResponse, GenericInvoiceResponse,
+    MultipleInvoiceResponse, TaxiReceiptResponse, TrainTicketResponse, VatInvoiceResponse,
+};
+use crate::services::file::FileType;
+use crate::services::ocr::{Classification, OcrInput, OcrProvider};
+use async_trait::async_trait;
+use reqwest::Client;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 百度 OCR 默认服务地址，可通过配置 `baidu_ocr_base_url` 覆盖
+pub const BAIDU_DEFAULT_BASE_URL: &str = "https://aip.baidubce.com";
+
+const BAIDU_TOKEN_PATH: &str = "/oauth/2.0/token";
+const BAIDU_VAT_INVOICE_PATH: &str = "/rest/2.0/ocr/v1/vat_invoice";
+const BAIDU_INVOICE_PATH: &str = "/rest/2.0/ocr/v1/invoice";
+const BAIDU_TRAIN_TICKET_PATH: &str = "/rest/2.0/ocr/v1/train_ticket";
+const BAIDU_TAXI_RECEIPT_PATH: &str = "/rest/2.0/ocr/v1/taxi_receipt";
+const BAIDU_AIR_TICKET_PATH: &str = "/rest/2.0/ocr/v1/air_ticket";
+const BAIDU_MULTIPLE_INVOICE_PATH: &str = "/rest/2.0/ocr/v1/multiple_invoice";
+
+/// 鉴权相关的错误码：无权限、IAM 鉴权失败、token 无效或过期
+const AUTH_ERROR_CODES: [i32; 4] = [6, 14, 110, 111];
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 3600;
+
+/// 百度 OCR 服务商
+pub struct BaiduOcrProvider {
+    client: Client,
+    /// 显式指定的 API Key / Secret Key，未指定时从配置读取
+    credentials: Option<(String, String)>,
+    /// 显式指定的服务地址，未指定时从配置读取
+    base_url: Option<String>,
+}
+
+impl BaiduOcrProvider {
+    pub const NAME: &'static str = "baidu";
+
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            credentials: None,
+            base_url: None,
+        }
+    }
+
+    /// 使用指定凭证创建（用于测试连接）
+    pub fn with_credentials(api_key: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Self {
+            credentials: Some((api_key.into(), secret_key.into())),
+            ..Self::new()
+        }
+    }
+
+    /// 使用指定服务地址（如本地 mock 服务）
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// 获取服务地址，优先级：显式指定 > 配置 > 默认
+    fn base_url(&self) -> Result<String, AppError> {
+        let base_url = match &self.base_url {
+            Some(url) => url.clone(),
+            None => config_repo::get_config(config_keys::BAIDU_OCR_BASE_URL)?
+                .filter(|url| !url.trim().is_empty())
+                .unwrap_or_else(|| BAIDU_DEFAULT_BASE_URL.to_string()),
+        };
+        Ok(base_url.trim().trim_end_matches('/').to_string())
+    }
+
+    /// 获取 API 凭证
+    fn credentials(&self) -> Result<(String, String), AppError> {
+        if let Some(credentials) = &self.credentials {
+            return Ok(credentials.clone());
+        }
+        let api_key = config_repo::get_config(config_keys::BAIDU_OCR_API_KEY)?
+            .ok_or_else(|| AppError::Config("未配置百度OCR API Key".to_string()))?;
+        let secret_key = config_repo::get_config(config_keys::BAIDU_OCR_SECRET_KEY)?
+            .ok_or_else(|| AppError::Config("未配置百度OCR Secret Key".to_string()))?;
+        Ok((api_key, secret_key))
+    }
+
+    /// 获取 access_token，优先从数据库缓存获取，过期则刷新
+    pub async fn get_access_token(&self) -> Result<String, AppError> {
+        if self.credentials.is_none() {
+            if let Some(cached) = self.get_cached_token()? {
+                return Ok(cached);
+            }
+        }
+        let (api_key, secret_key) = self.credentials()?;
+        self.refresh_token(&api_key, &secret_key).await
+    }
+
+    fn get_cached_token(&self) -> Result<Option<String>, AppError> {
+        let token = config_repo::get_config(config_keys::BAIDU_OCR_ACCESS_TOKEN)?;
+        let expires = config_repo::get_config(config_keys::BAIDU_OCR_TOKEN_EXPIRES)?;
+
+        if let (Some(token), Some(expires_str)) = (token, expires) {
+            if let Ok(expires_ts) = expires_str.parse::<i64>() {
+                let now = current_timestamp();
+                if now < expires_ts - TOKEN_REFRESH_MARGIN_SECS {
+                    return Ok(Some(token));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn refresh_token(&self, api_key: &str, secret_key: &str) -> Result<String, AppError> {
+        let url = format!(
+            "{}{}?grant_type=client_credentials&client_id={}&client_secret={}",
+            self.base_url()?,
+            BAIDU_TOKEN_PATH,
+            api_key,
+            secret_key
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .send()
+            .await
+            .map_err(|e| AppError::Ocr(format!("获取token请求失败: {}", e)))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Ocr(format!("读取响应失败: {}", e)))?;
+
+        if !status.is_success() {
+            return Err(parse_error_response(&body));
+        }
+
+        let token_resp: BaiduTokenResponse = serde_json::from_str(&body)
+            .map_err(|e| AppError::Ocr(format!("解析token响应失败: {}", e)))?;
+
+        let expires_at = current_timestamp() + token_resp.expires_in;
+        config_repo::set_config(
+            config_keys::BAIDU_OCR_ACCESS_TOKEN,
+            &token_resp.access_token,
+            Some("百度OCR Access Token"),
+        )?;
+        config_repo::set_config(
+            config_keys::BAIDU_OCR_TOKEN_EXPIRES,
+            &expires_at.to_string(),
+            Some("Token过期时间戳"),
+        )?;
+
+        Ok(token_resp.access_token)
+    }
+
+    async fn call_ocr_api<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        input: OcrInput<'_>,
+    ) -> Result<T, AppError> {
+        let token = self.get_access_token().await?;
+        let url = format!("{}{}?access_token={}", self.base_url()?, path, token);
+
+        let page = input.page.unwrap_or(1).to_string();
+        let params: Vec<(&str, &str)> = if *input.file_type == FileType::Pdf {
+            vec![("pdf_file", input.file_base64), ("pdf_file_num", &page)]
+        } else {
+            vec![("image", input.file_base64)]
+        };
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| AppError::Ocr(format!("OCR请求失败: {}", e)))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Ocr(format!("读取OCR响应失败: {}", e)))?;
+
+        if !status.is_success() {
+            return Err(parse_error_response(&body));
+        }
+
+        if let Ok(err) = serde_json::from_str::<BaiduErrorResponse>(&body) {
+            if err.error_code.is_some() || err.error.is_some() {
+                return Err(parse_error_response(&body));
+            }
+        }
+
+        serde_json::from_str(&body).map_err(|e| AppError::Ocr(format!("解析OCR响应失败: {}", e)))
+    }
+}
+
+impl Default for BaiduOcrProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl OcrProvider for BaiduOcrProvider {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn authenticate(&self) -> Result<(), AppError> {
+        self.get_access_token().await?;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<bool, AppError> {
+        let (api_key, secret_key) = self.credentials()?;
+        self.refresh_token(&api_key, &secret_key).await?;
+        Ok(true)
+    }
+
+    async fn classify(&self, input: OcrInput<'_>) -> Result<Option<Classification>, AppError> {
+        let response = self.recognize_multiple(input).await?;
+
+        // 取置信度最高的票据
+        let best = response.words_result.iter().max_by(|a, b| {
+            a.probability
+                .unwrap_or(0.0)
+                .total_cmp(&b.probability.unwrap_or(0.0))
+        });
+
+        Ok(best
+            .map(|item| item.invoice_type())
+            .filter(|invoice_type| *invoice_type != InvoiceType::Other)
+            .map(|invoice_type| Classification {
+                invoice_type,
+                classifier: self.multiple_invoice_classifier(),
+            }))
+    }
+
+    async fn recognize_multiple(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<MultipleInvoiceResponse, AppError> {
+        self.call_ocr_api(BAIDU_MULTIPLE_INVOICE_PATH, input).await
+    }
+
+    async fn recognize_vat_invoice(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<VatInvoiceResponse, AppError> {
+        self.call_ocr_api(BAIDU_VAT_INVOICE_PATH, input).await
+    }
+
+    async fn recognize_generic(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<GenericInvoiceResponse, AppError> {
+        self.call_ocr_api(BAIDU_INVOICE_PATH, input).await
+    }
+
+    async fn recognize_train_ticket(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<TrainTicketResponse, AppError> {
+        self.call_ocr_api(BAIDU_TRAIN_TICKET_PATH, input).await
+    }
+
+    async fn recognize_taxi_receipt(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<TaxiReceiptResponse, AppError> {
+        self.call_ocr_api(BAIDU_TAXI_RECEIPT_PATH, input).await
+    }
+
+    async fn recognize_air_ticket(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<AirTicketResponse, AppError> {
+        self.call_ocr_api(BAIDU_AIR_TICKET_PATH, input).await
+    }
+}
+
+fn current_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn parse_error_response(body: &str) -> AppError {
+    if let Ok(err) = serde_json::from_str::<BaiduErrorResponse>(body) {
+        // token 接口以 error 字段返回凭证错误，识别接口以错误码返回
+        let is_auth = err.error.is_some()
+            || err
+                .error_code
+                .is_some_and(|code| AUTH_ERROR_CODES.contains(&code));
+        let msg = err
+            .error_msg
+            .or(err.error_description)
+            .or(err.error)
+            .unwrap_or_else(|| "未知错误".to_string());
+        return if is_auth {
+            AppError::Auth(msg)
+        } else {
+            AppError::Ocr(msg)
+        };
+    }
+    AppError::Ocr(format!("API错误: {}", body))
+}
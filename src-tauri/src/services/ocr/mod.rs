@@ -0,0 +1,128 @@
+pub mod baidu;
+
+use crate::db::config_repo;
+use crate::error::AppError;
+use crate::models::config::config_keys;
+use crate::models::invoice::InvoiceType;
+use crate::models::ocr_response::{
+    AirTicketResponse, GenericInvoiceResponse, MultipleInvoiceResponse, TaxiReceiptResponse,
+    TrainTicketResponse, VatInvoiceResponse,
+};
+use crate::services::file::FileType;
+use async_trait::async_trait;
+
+pub use baidu::BaiduOcrProvider;
+
+/// 默认 OCR 服务商
+pub const DEFAULT_OCR_PROVIDER: &str = BaiduOcrProvider::NAME;
+
+/// 提交给 OCR 服务商的文件
+#[derive(Debug, Clone, Copy)]
+pub struct OcrInput<'a> {
+    /// base64 编码的文件内容
+    pub file_base64: &'a str,
+    pub file_type: &'a FileType,
+    /// PDF 页码（从 1 开始），图片为 `None`
+    pub page: Option<u32>,
+}
+
+/// 票种分类结果
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub invoice_type: InvoiceType,
+    /// 做出判断的分类器，如 baidu_multiple_invoice
+    pub classifier: String,
+}
+
+/// OCR 服务商接口
+///
+/// 每个服务商负责自己的鉴权与接口调用，并把识别结果转换为统一的响应模型，
+/// `InvoiceService` 只依赖该接口。
+#[async_trait]
+pub trait OcrProvider: Send + Sync {
+    /// 服务商标识（与配置值一致）
+    fn name(&self) -> &'static str;
+
+    /// 完成鉴权（获取或刷新凭证）
+    async fn authenticate(&self) -> Result<(), AppError>;
+
+    /// 检查服务是否可用、凭证是否有效
+    async fn health_check(&self) -> Result<bool, AppError>;
+
+    /// 识别增值税发票
+    async fn recognize_vat_invoice(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<VatInvoiceResponse, AppError>;
+
+    /// 判断票据类型，服务商不支持分类时返回 `None`
+    async fn classify(
+        &self,
+        _input: OcrInput<'_>,
+    ) -> Result<Option<Classification>, AppError> {
+        Ok(None)
+    }
+
+    /// 识别一张图片中的多张票据（含票种与位置）
+    async fn recognize_multiple(
+        &self,
+        _input: OcrInput<'_>,
+    ) -> Result<MultipleInvoiceResponse, AppError> {
+        Err(unsupported(self.name(), "多票据混贴"))
+    }
+
+    /// 多票据识别接口对应的分类器标识
+    fn multiple_invoice_classifier(&self) -> String {
+        format!("{}_multiple_invoice", self.name())
+    }
+
+    /// 识别通用票据
+    async fn recognize_generic(
+        &self,
+        input: OcrInput<'_>,
+    ) -> Result<GenericInvoiceResponse, AppError>;
+
+    /// 识别火车票
+    async fn recognize_train_ticket(
+        &self,
+        _input: OcrInput<'_>,
+    ) -> Result<TrainTicketResponse, AppError> {
+        Err(unsupported(self.name(), "火车票"))
+    }
+
+    /// 识别出租车票
+    async fn recognize_taxi_receipt(
+        &self,
+        _input: OcrInput<'_>,
+    ) -> Result<TaxiReceiptResponse, AppError> {
+        Err(unsupported(self.name(), "出租车票"))
+    }
+
+    /// 识别机票行程单
+    async fn recognize_air_ticket(
+        &self,
+        _input: OcrInput<'_>,
+    ) -> Result<AirTicketResponse, AppError> {
+        Err(unsupported(self.name(), "机票行程单"))
+    }
+}
+
+/// 服务商不支持某类票据时的错误
+fn unsupported(provider: &str, kind: &str) -> AppError {
+    AppError::Ocr(format!("OCR服务商 {} 不支持识别{}", provider, kind))
+}
+
+/// 按配置创建 OCR 服务商，未配置时使用百度
+pub fn create_provider() -> Result<Box<dyn OcrProvider>, AppError> {
+    let name = config_repo::get_config(config_keys::OCR_PROVIDER)?
+        .unwrap_or_else(|| DEFAULT_OCR_PROVIDER.to_string());
+    create_provider_by_name(&name)
+}
+
+/// 按名称创建 OCR 服务商
+pub fn create_provider_by_name(name: &str) -> Result<Box<dyn OcrProvider>, AppError> {
+    match name.trim().to_lowercase().as_str() {
+        BaiduOcrProvider::NAME => Ok(Box::new(BaiduOcrProvider::new())),
+        other => Err(AppError::Config(format!("不支持的OCR服务商: {}", other))),
+    }
+}
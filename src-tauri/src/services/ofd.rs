@@ -0,0 +1,407 @@
+//! OFD 电子发票本地解析
+//!
+//! OFD 是 zip 包，数电票会在附件中带一份完整的发票 XML（根节点 `EInvoice`）；
+//! 早期的增值税电子发票没有该附件，字段通过 `CustomTags.xml` 指向版面中的文字对象。
+
+use crate::error::AppError;
+use crate::models::invoice::{Invoice, InvoiceType};
+use crate::models::money::Money;
+use crate::models::ocr_response::{invalid_date, parse_date, parse_money, CommodityItem};
+use crate::models::warning::InvoiceWarning;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// 解析 OFD 时记录的分类器标识
+pub const CLASSIFIER_OFD: &str = "ofd";
+
+pub struct OfdService;
+
+impl OfdService {
+    /// 解析 OFD 发票文件
+    pub fn parse_file(path: &Path) -> Result<Invoice, AppError> {
+        let data =
+            fs::read(path).map_err(|e| AppError::FileProcess(format!("读取文件失败: {}", e)))?;
+        let mut invoice = Self::parse_bytes(&data)?;
+        invoice.original_file_path = Some(path.to_string_lossy().to_string());
+        Ok(invoice)
+    }
+
+    /// 解析 OFD 文件内容
+    pub fn parse_bytes(data: &[u8]) -> Result<Invoice, AppError> {
+        let entries = read_xml_entries(data)?;
+
+        let fields = entries
+            .values()
+            .find_map(|xml| parse_einvoice(xml))
+            .or_else(|| parse_custom_tags(&entries))
+            .ok_or_else(|| AppError::FileProcess("OFD 文件中未找到发票数据".to_string()))?;
+
+        Ok(fields.into_invoice())
+    }
+}
+
+/// 从 OFD 中读出的发票字段
+#[derive(Debug, Default)]
+struct OfdFields {
+    invoice_type: Option<String>,
+    invoice_code: Option<String>,
+    invoice_number: Option<String>,
+    invoice_date: Option<String>,
+    amount_without_tax: Option<String>,
+    tax_amount: Option<String>,
+    total_amount: Option<String>,
+    buyer_name: Option<String>,
+    buyer_tax_number: Option<String>,
+    seller_name: Option<String>,
+    seller_tax_number: Option<String>,
+    check_code: Option<String>,
+    machine_code: Option<String>,
+    items: Vec<String>,
+    remark: Option<String>,
+}
+
+impl OfdFields {
+    fn into_invoice(self) -> Invoice {
+        let total = parse_money(self.total_amount.as_deref()).unwrap_or(Money::ZERO);
+        let mut invoice = Invoice::new(parse_invoice_type(self.invoice_type.as_deref()), total);
+
+        invoice.invoice_code = self.invoice_code;
+        invoice.invoice_number = self.invoice_number;
+        // 开票时间可能带时分秒，只保留日期
+        let date = self
+            .invoice_date
+            .as_deref()
+            .and_then(|s| s.split_whitespace().next());
+        invoice.invoice_date = parse_date(date);
+        if let Some(warning) = invalid_date(date) {
+            InvoiceWarning::append_to(&mut invoice.warnings, warning);
+        }
+        invoice.amount_without_tax = parse_money(self.amount_without_tax.as_deref());
+        invoice.tax_amount = parse_money(self.tax_amount.as_deref());
+        invoice.buyer_name = self.buyer_name;
+        invoice.buyer_tax_number = self.buyer_tax_number;
+        invoice.seller_name = self.seller_name;
+        invoice.seller_tax_number = self.seller_tax_number;
+        invoice.check_code = self.check_code;
+        invoice.machine_code = self.machine_code;
+        invoice.remark = self.remark;
+
+        if !self.items.is_empty() {
+            invoice.commodity_name = Some(self.items.join("; "));
+            let detail: Vec<CommodityItem> = self
+                .items
+                .into_iter()
+                .enumerate()
+                .map(|(i, word)| CommodityItem {
+                    word,
+                    row: Some((i + 1).to_string()),
+                })
+                .collect();
+            invoice.commodity_detail = serde_json::to_string(&detail).ok();
+        }
+
+        invoice.file_type = Some("ofd".to_string());
+        invoice.ocr_confidence = Some(1.0);
+        invoice.classified_by = Some(CLASSIFIER_OFD.to_string());
+        invoice
+    }
+}
+
+/// 按票面名称判断票种
+fn parse_invoice_type(name: Option<&str>) -> InvoiceType {
+    match name {
+        Some(s) if s.contains("铁路") => InvoiceType::TrainTicket,
+        Some(s) if s.contains("航空") || s.contains("行程单") => InvoiceType::FlightItinerary,
+        Some(s) if s.contains("专用") => InvoiceType::VatInvoice,
+        _ => InvoiceType::VatElectronicInvoice,
+    }
+}
+
+/// 读取 zip 包中的全部 XML 文件，键为包内路径
+fn read_xml_entries(data: &[u8]) -> Result<HashMap<String, String>, AppError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))
+        .map_err(|e| AppError::FileProcess(format!("解析OFD文件失败: {}", e)))?;
+
+    let mut entries = HashMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| AppError::FileProcess(format!("解析OFD文件失败: {}", e)))?;
+        if !file.is_file() || !file.name().to_lowercase().ends_with(".xml") {
+            continue;
+        }
+        let name = file.name().trim_start_matches('/').to_string();
+        let mut content = String::new();
+        if file.read_to_string(&mut content).is_ok() {
+            entries.insert(name, content);
+        }
+    }
+    Ok(entries)
+}
+
+/// 解析数电票附带的发票 XML
+fn parse_einvoice(xml: &str) -> Option<OfdFields> {
+    let doc = Document::parse(xml).ok()?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "EInvoice" {
+        return None;
+    }
+
+    let text = |path: &[&str]| find_text(root, path);
+    Some(OfdFields {
+        invoice_type: text(&["Header", "InherentLabel", "EInvoiceType", "LabelName"]),
+        invoice_number: text(&["TaxSupervisionInfo", "InvoiceNumber"]),
+        invoice_date: text(&["TaxSupervisionInfo", "IssueTime"])
+            .or_else(|| text(&["EInvoiceData", "BasicInformation", "RequestTime"])),
+        amount_without_tax: text(&["EInvoiceData", "BasicInformation", "TotalAmWithoutTax"]),
+        tax_amount: text(&["EInvoiceData", "BasicInformation", "TotalTaxAm"]),
+        total_amount: text(&[
+            "EInvoiceData",
+            "BasicInformation",
+            "TotalTax-includedAmount",
+        ]),
+        buyer_name: text(&["EInvoiceData", "BuyerInformation", "BuyerName"]),
+        buyer_tax_number: text(&["EInvoiceData", "BuyerInformation", "BuyerIdNum"]),
+        seller_name: text(&["EInvoiceData", "SellerInformation", "SellerName"]),
+        seller_tax_number: text(&["EInvoiceData", "SellerInformation", "SellerIdNum"]),
+        items: root
+            .descendants()
+            .filter(|n| n.has_tag_name("IssuItemInformation"))
+            .filter_map(|n| find_text(n, &["ItemName"]))
+            .collect(),
+        remark: text(&["EInvoiceData", "AdditionalInformation", "Remark"]),
+        ..Default::default()
+    })
+}
+
+/// 通过 CustomTags.xml 把字段映射到版面文字对象
+fn parse_custom_tags(entries: &HashMap<String, String>) -> Option<OfdFields> {
+    let (tags_path, tags_xml) = entries
+        .iter()
+        .find(|(name, _)| name.ends_with("CustomTags.xml"))?;
+    let tags_doc = Document::parse(tags_xml).ok()?;
+    let base_dir = tags_path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+
+    let texts = text_objects(entries);
+    let mut values: HashMap<String, String> = HashMap::new();
+
+    for file_loc in tags_doc
+        .descendants()
+        .filter(|n| n.has_tag_name("FileLoc"))
+        .filter_map(|n| n.text())
+    {
+        let Some(tag_xml) = entries.get(&resolve_path(base_dir, file_loc.trim())) else {
+            continue;
+        };
+        let Ok(tag_doc) = Document::parse(tag_xml) else {
+            continue;
+        };
+        // 叶子字段下是一个或多个 ObjectRef，按顺序拼接引用的文字
+        for field in tag_doc
+            .descendants()
+            .filter(|n| n.is_element() && n.children().any(|c| c.has_tag_name("ObjectRef")))
+        {
+            let value: String = field
+                .children()
+                .filter(|c| c.has_tag_name("ObjectRef"))
+                .filter_map(|c| c.text())
+                .filter_map(|id| texts.get(id.trim()))
+                .map(String::as_str)
+                .collect();
+            if !value.is_empty() {
+                values.insert(field.tag_name().name().to_string(), value);
+            }
+        }
+    }
+
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut get = |key: &str| values.remove(key);
+    Some(OfdFields {
+        invoice_type: get("InvoiceType"),
+        invoice_code: get("InvoiceCode"),
+        invoice_number: get("InvoiceNo"),
+        invoice_date: get("IssueDate"),
+        amount_without_tax: get("TaxExclusiveTotalAmount"),
+        tax_amount: get("TaxTotalAmount"),
+        total_amount: get("TaxInclusiveTotalAmount"),
+        buyer_name: get("BuyerName"),
+        buyer_tax_number: get("BuyerTaxID"),
+        seller_name: get("SellerName"),
+        seller_tax_number: get("SellerTaxID"),
+        check_code: get("InvoiceCheckCode"),
+        machine_code: get("MachineNo"),
+        items: Vec::new(),
+        remark: get("Note"),
+    })
+}
+
+/// 收集所有页面中的文字对象，键为对象 ID
+fn text_objects(entries: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut texts = HashMap::new();
+    for xml in entries
+        .iter()
+        .filter(|(name, _)| name.ends_with("Content.xml"))
+        .map(|(_, xml)| xml)
+    {
+        let Ok(doc) = Document::parse(xml) else {
+            continue;
+        };
+        for node in doc.descendants().filter(|n| n.has_tag_name("TextObject")) {
+            if let Some(id) = node.attribute("ID") {
+                let text: String = node
+                    .children()
+                    .filter(|c| c.has_tag_name("TextCode"))
+                    .filter_map(|c| c.text())
+                    .collect();
+                texts.insert(id.to_string(), text);
+            }
+        }
+    }
+    texts
+}
+
+/// 按元素名逐级查找并返回去除首尾空白的文本
+fn find_text(node: Node, path: &[&str]) -> Option<String> {
+    let mut current = node;
+    for name in path {
+        current = current.children().find(|c| c.has_tag_name(*name))?;
+    }
+    current
+        .text()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// 解析包内相对路径（以 `/` 开头时相对包根目录）
+fn resolve_path(base_dir: &str, loc: &str) -> String {
+    match loc.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None if base_dir.is_empty() => loc.to_string(),
+        None => format!("{}/{}", base_dir, loc),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn build_ofd(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_parse_einvoice_attachment() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <EInvoice>
+              <Header>
+                <InherentLabel>
+                  <EInvoiceType><LabelCode>01</LabelCode><LabelName>增值税专用发票</LabelName></EInvoiceType>
+                </InherentLabel>
+              </Header>
+              <EInvoiceData>
+                <SellerInformation>
+                  <SellerIdNum>91330100MA2B3C4D5E</SellerIdNum>
+                  <SellerName>杭州示例办公用品有限公司</SellerName>
+                </SellerInformation>
+                <BuyerInformation>
+                  <BuyerIdNum>91110000100000000X</BuyerIdNum>
+                  <BuyerName>北京示例科技有限公司</BuyerName>
+                </BuyerInformation>
+                <BasicInformation>
+                  <TotalAmWithoutTax>1000.00</TotalAmWithoutTax>
+                  <TotalTaxAm>130.00</TotalTaxAm>
+                  <TotalTax-includedAmount>1130.00</TotalTax-includedAmount>
+                </BasicInformation>
+                <IssuItemInformation><ItemName>*纸制品*打印纸</ItemName></IssuItemInformation>
+                <IssuItemInformation><ItemName>*文具*签字笔</ItemName></IssuItemInformation>
+              </EInvoiceData>
+              <TaxSupervisionInfo>
+                <InvoiceNumber>24332000000012345678</InvoiceNumber>
+                <IssueTime>2024-01-15 10:20:30</IssueTime>
+              </TaxSupervisionInfo>
+            </EInvoice>"#;
+        let data = build_ofd(&[
+            (
+                "OFD.xml",
+                "<ofd:OFD xmlns:ofd=\"http://www.ofdspec.org/2016\"/>",
+            ),
+            ("Doc_0/Attachs/original_invoice.xml", xml),
+        ]);
+
+        let invoice = OfdService::parse_bytes(&data).unwrap();
+        assert_eq!(invoice.invoice_type, InvoiceType::VatInvoice);
+        assert_eq!(
+            invoice.invoice_number.as_deref(),
+            Some("24332000000012345678")
+        );
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-01-15"));
+        assert_eq!(invoice.total_amount, Money::from_fen(113000));
+        assert_eq!(invoice.tax_amount, Some(Money::from_fen(13000)));
+        assert_eq!(
+            invoice.seller_tax_number.as_deref(),
+            Some("91330100MA2B3C4D5E")
+        );
+        assert_eq!(
+            invoice.commodity_name.as_deref(),
+            Some("*纸制品*打印纸; *文具*签字笔")
+        );
+        assert_eq!(invoice.ocr_confidence, Some(1.0));
+    }
+
+    #[test]
+    fn test_parse_custom_tags() {
+        let data = build_ofd(&[
+            (
+                "Doc_0/CustomTags.xml",
+                r#"<ofd:CustomTags xmlns:ofd="http://www.ofdspec.org/2016">
+                     <ofd:CustomTag TypeID="0"><ofd:FileLoc>CustomTag.xml</ofd:FileLoc></ofd:CustomTag>
+                   </ofd:CustomTags>"#,
+            ),
+            (
+                "Doc_0/CustomTag.xml",
+                r#"<eInvoice xmlns:ofd="http://www.ofdspec.org/2016">
+                     <InvoiceCode><ofd:ObjectRef PageRef="1">5</ofd:ObjectRef></InvoiceCode>
+                     <InvoiceNo><ofd:ObjectRef PageRef="1">6</ofd:ObjectRef></InvoiceNo>
+                     <IssueDate><ofd:ObjectRef PageRef="1">7</ofd:ObjectRef></IssueDate>
+                     <TaxInclusiveTotalAmount><ofd:ObjectRef PageRef="1">8</ofd:ObjectRef></TaxInclusiveTotalAmount>
+                     <Seller><SellerName><ofd:ObjectRef PageRef="1">9</ofd:ObjectRef></SellerName></Seller>
+                   </eInvoice>"#,
+            ),
+            (
+                "Doc_0/Pages/Page_0/Content.xml",
+                r#"<ofd:Page xmlns:ofd="http://www.ofdspec.org/2016"><ofd:Content><ofd:Layer>
+                     <ofd:TextObject ID="5"><ofd:TextCode>033001900111</ofd:TextCode></ofd:TextObject>
+                     <ofd:TextObject ID="6"><ofd:TextCode>12345678</ofd:TextCode></ofd:TextObject>
+                     <ofd:TextObject ID="7"><ofd:TextCode>2021年06月01日</ofd:TextCode></ofd:TextObject>
+                     <ofd:TextObject ID="8"><ofd:TextCode>¥226.00</ofd:TextCode></ofd:TextObject>
+                     <ofd:TextObject ID="9"><ofd:TextCode>示例餐饮有限公司</ofd:TextCode></ofd:TextObject>
+                   </ofd:Layer></ofd:Content></ofd:Page>"#,
+            ),
+        ]);
+
+        let invoice = OfdService::parse_bytes(&data).unwrap();
+        assert_eq!(invoice.invoice_type, InvoiceType::VatElectronicInvoice);
+        assert_eq!(invoice.invoice_code.as_deref(), Some("033001900111"));
+        assert_eq!(invoice.invoice_number.as_deref(), Some("12345678"));
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2021-06-01"));
+        assert_eq!(invoice.total_amount, Money::from_fen(22600));
+        assert_eq!(invoice.seller_name.as_deref(), Some("示例餐饮有限公司"));
+    }
+}
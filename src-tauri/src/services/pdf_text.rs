@@ -0,0 +1,151 @@
+//! PDF 电子发票文字层解析
+//!
+//! 税务系统开具的 PDF 电子发票带有文字层，按标准版式提取字段即可得到发票，
+//! 只有扫描件等没有文字层或字段不全的页面才需要走 OCR。
+
+use crate::models::invoice::{Invoice, InvoiceType};
+use crate::models::money::Money;
+use crate::models::ocr_response::{parse_date, parse_money};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+/// 从文字层解析时记录的分类器标识
+pub const CLASSIFIER_PDF_TEXT: &str = "pdf_text";
+
+static INVOICE_CODE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"发票代码[:：]?(\d{10,12})").unwrap());
+static INVOICE_NUMBER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"发票号码[:：]?(\d{8,20})").unwrap());
+static DATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"开票日期[:：]?(\d{4}年\d{1,2}月\d{1,2}日|\d{4}-\d{1,2}-\d{1,2})").unwrap()
+});
+static CHECK_CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"校验码[:：]?(\d{20})").unwrap());
+static MACHINE_CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"机器编号[:：]?(\d{12})").unwrap());
+static NAME_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"名称[:：]([^\n:：]+?)(?:统一社会信用代码|纳税人识别号|\n|$)").unwrap()
+});
+static TAX_NUMBER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"纳税人识别号[:：]?([0-9A-Z]{15,20})").unwrap());
+/// 合计行：不含税金额 + 税额
+static SUBTOTAL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"合计[¥￥](\d+(?:\.\d{1,2})?)[¥￥](\d+(?:\.\d{1,2})?|\*+)").unwrap());
+static TOTAL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[（(]小写[）)][¥￥]?(\d+(?:\.\d{1,2})?)").unwrap());
+
+pub struct PdfTextService;
+
+impl PdfTextService {
+    /// 按页提取 PDF 文字层，无法解析时返回空列表
+    pub fn extract_pages(path: &Path) -> Vec<String> {
+        let Ok(data) = std::fs::read(path) else {
+            return Vec::new();
+        };
+        // pdf-extract 遇到个别不规范的字体会 panic，这里当作没有文字层处理
+        std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(&data))
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or_default()
+    }
+
+    /// 按电子发票标准版式解析一页文字，关键字段不全时返回 `None`
+    pub fn parse_invoice(text: &str) -> Option<Invoice> {
+        // 文字层常把标签拆成单字（如 "校 验 码"），去掉行内空白后再匹配
+        let text: String = text
+            .lines()
+            .map(|line| line.split_whitespace().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let invoice_number = capture(&INVOICE_NUMBER_RE, &text)?;
+        let invoice_date = parse_date(capture(&DATE_RE, &text).as_deref())?;
+        let total = parse_money(capture(&TOTAL_RE, &text).as_deref())?;
+
+        let names: Vec<String> = NAME_RE
+            .captures_iter(&text)
+            .map(|caps| caps[1].to_string())
+            .collect();
+        let tax_numbers: Vec<String> = TAX_NUMBER_RE
+            .captures_iter(&text)
+            .map(|caps| caps[1].to_string())
+            .collect();
+        // 版式中购买方在前、销售方在后
+        let seller_name = names.get(1).cloned();
+        let seller_tax_number = tax_numbers.get(1).cloned();
+        if seller_name.is_none() && seller_tax_number.is_none() {
+            return None;
+        }
+
+        let invoice_type = if text.contains("专用发票") {
+            InvoiceType::VatInvoice
+        } else {
+            InvoiceType::VatElectronicInvoice
+        };
+        let mut invoice = Invoice::new(invoice_type, total);
+
+        invoice.invoice_code = capture(&INVOICE_CODE_RE, &text);
+        invoice.invoice_number = Some(invoice_number);
+        invoice.invoice_date = Some(invoice_date);
+        if let Some(caps) = SUBTOTAL_RE.captures(&text) {
+            invoice.amount_without_tax = parse_money(Some(&caps[1]));
+            // 免税发票税额显示为 ***
+            invoice.tax_amount = parse_money(Some(&caps[2])).or(Some(Money::ZERO));
+        }
+        invoice.buyer_name = names.first().cloned();
+        invoice.buyer_tax_number = tax_numbers.first().cloned();
+        invoice.seller_name = seller_name;
+        invoice.seller_tax_number = seller_tax_number;
+        invoice.check_code = capture(&CHECK_CODE_RE, &text);
+        invoice.machine_code = capture(&MACHINE_CODE_RE, &text);
+        invoice.file_type = Some("pdf".to_string());
+        invoice.ocr_confidence = Some(1.0);
+        invoice.classified_by = Some(CLASSIFIER_PDF_TEXT.to_string());
+
+        Some(invoice)
+    }
+}
+
+/// 取正则第一个捕获组
+fn capture(re: &Regex, text: &str) -> Option<String> {
+    re.captures(text).map(|caps| caps[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_electronic_invoice_text() {
+        let text = "电子发票（普通发票）\n\
+            发票号码：24332000000012345678\n\
+            开票日期：2024年01月15日\n\
+            购 名称：北京示例科技有限公司\n\
+            买 统一社会信用代码/纳税人识别号：91110000100000000X\n\
+            销 名称：杭州示例办公用品有限公司\n\
+            售 统一社会信用代码/纳税人识别号：91330100MA2B3C4D5E\n\
+            *纸制品*打印纸 箱 2 500.00 1000.00 13% 130.00\n\
+            合 计 ¥1000.00 ¥130.00\n\
+            价税合计（大写） 壹仟壹佰叁拾圆整 （小写）¥1130.00\n";
+
+        let invoice = PdfTextService::parse_invoice(text).unwrap();
+        assert_eq!(invoice.invoice_type, InvoiceType::VatElectronicInvoice);
+        assert_eq!(
+            invoice.invoice_number.as_deref(),
+            Some("24332000000012345678")
+        );
+        assert_eq!(invoice.invoice_date.as_deref(), Some("2024-01-15"));
+        assert_eq!(invoice.total_amount, Money::from_fen(113000));
+        assert_eq!(invoice.amount_without_tax, Some(Money::from_fen(100000)));
+        assert_eq!(invoice.tax_amount, Some(Money::from_fen(13000)));
+        assert_eq!(invoice.buyer_name.as_deref(), Some("北京示例科技有限公司"));
+        assert_eq!(
+            invoice.seller_tax_number.as_deref(),
+            Some("91330100MA2B3C4D5E")
+        );
+
+        // 缺少价税合计时交给 OCR
+        assert!(
+            PdfTextService::parse_invoice("发票号码：12345678\n开票日期：2024年01月15日").is_none()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! 图片预处理：透视裁剪、纠偏、灰度与对比度增强
+//!
+//! EXIF 方向在解码时处理（见 `FileService::preprocess_page`）。
+
+use image::{imageops, DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+use imageproc::contours::{find_contours, BorderType};
+use imageproc::contrast::{otsu_level, stretch_contrast, threshold, ThresholdType};
+use imageproc::filter::gaussian_blur_f32;
+use imageproc::geometric_transformations::{
+    rotate_about_center, warp_into, Interpolation, Projection,
+};
+use imageproc::geometry::{approximate_polygon_dp, arc_length, contour_area, min_area_rect};
+use serde::{Deserialize, Serialize};
+
+/// 估算倾斜角时的最大搜索范围（度）
+const MAX_SKEW_DEGREES: f32 = 10.0;
+/// 估算倾斜角时的搜索步长（度）
+const SKEW_STEP_DEGREES: f32 = 0.25;
+/// 低于该角度（度）不做旋转，避免无谓的插值模糊
+const MIN_SKEW_DEGREES: f32 = 0.3;
+/// 分析用缩略图的最长边
+const ANALYSIS_SIZE: u32 = 800;
+
+/// 单次识别的预处理选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessOptions {
+    /// 按 EXIF 方向摆正
+    pub auto_rotate: bool,
+    /// 纠正文字倾斜
+    pub deskew: bool,
+    /// 按票据边缘透视裁剪
+    pub crop: bool,
+    /// 转灰度、去阴影并拉伸对比度
+    pub enhance: bool,
+}
+
+impl PreprocessOptions {
+    /// 是否启用了任一处理
+    pub fn is_enabled(&self) -> bool {
+        self.auto_rotate || self.deskew || self.crop || self.enhance
+    }
+}
+
+pub struct ImagePreprocessor;
+
+impl ImagePreprocessor {
+    /// 按选项依次裁剪、纠偏、增强
+    pub fn process(img: DynamicImage, options: &PreprocessOptions) -> DynamicImage {
+        let mut img = img;
+        if options.crop {
+            if let Some(cropped) = Self::crop_to_document(&img) {
+                img = cropped;
+            }
+        }
+        if options.deskew {
+            img = Self::deskew(img);
+        }
+        if options.enhance {
+            img = Self::enhance(&img);
+        }
+        img
+    }
+
+    /// 找到票据的四个角并透视拉正，找不到明显的边缘时返回 `None`
+    fn crop_to_document(img: &DynamicImage) -> Option<DynamicImage> {
+        let (scale, small) = Self::analysis_image(img);
+        let blurred = gaussian_blur_f32(&small, 2.0);
+        // 票据通常比背景亮
+        let binary = threshold(&blurred, otsu_level(&blurred), ThresholdType::Binary);
+
+        let image_area = (small.width() * small.height()) as f64;
+        let contour = find_contours::<i32>(&binary)
+            .into_iter()
+            .filter(|c| c.border_type == BorderType::Outer)
+            .max_by(|a, b| contour_area(&a.points).total_cmp(&contour_area(&b.points)))?;
+        let area = contour_area(&contour.points);
+        if area < image_area * 0.2 || area > image_area * 0.98 {
+            return None;
+        }
+
+        let epsilon = arc_length(&contour.points, true) * 0.02;
+        let polygon = approximate_polygon_dp(&contour.points, epsilon, true);
+        let corners = if polygon.len() == 4 {
+            [polygon[0], polygon[1], polygon[2], polygon[3]]
+        } else {
+            min_area_rect(&contour.points)
+        };
+        let [tl, tr, br, bl] =
+            order_corners(corners.map(|p| (p.x as f32 / scale, p.y as f32 / scale)));
+
+        let width = distance(tl, tr).max(distance(bl, br)).round() as u32;
+        let height = distance(tl, bl).max(distance(tr, br)).round() as u32;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let (w, h) = (width as f32, height as f32);
+        let projection = Projection::from_control_points(
+            [tl, tr, br, bl],
+            [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)],
+        )?;
+
+        let mut out = RgbImage::new(width, height);
+        warp_into(
+            &img.to_rgb8(),
+            &projection,
+            Interpolation::Bilinear,
+            Rgb([255, 255, 255]),
+            &mut out,
+        );
+        Some(DynamicImage::ImageRgb8(out))
+    }
+
+    /// 按估算的倾斜角反向旋转
+    fn deskew(img: DynamicImage) -> DynamicImage {
+        let (_, small) = Self::analysis_image(&img);
+        let angle = Self::estimate_skew(&small);
+        if angle.abs() < MIN_SKEW_DEGREES {
+            return img;
+        }
+        DynamicImage::ImageRgb8(rotate_about_center(
+            &img.to_rgb8(),
+            -angle.to_radians(),
+            Interpolation::Bilinear,
+            Rgb([255, 255, 255]),
+        ))
+    }
+
+    /// 估算文字行的倾斜角（度，顺时针为正）
+    ///
+    /// 把深色像素按候选角度投影到纵轴，文字行与投影方向一致时直方图最集中。
+    pub fn estimate_skew(gray: &GrayImage) -> f32 {
+        let level = otsu_level(gray);
+        let dark: Vec<(f32, f32)> = gray
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[0] < level)
+            .map(|(x, y, _)| (x as f32, y as f32))
+            .collect();
+        if dark.is_empty() {
+            return 0.0;
+        }
+
+        let diagonal = (gray.width() as f32).hypot(gray.height() as f32) as usize;
+        let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES) as i32;
+        let mut bins = vec![0u32; diagonal * 2 + 1];
+
+        let mut best = (0.0, 0u64);
+        for step in -steps..=steps {
+            let angle = step as f32 * SKEW_STEP_DEGREES;
+            let (sin, cos) = angle.to_radians().sin_cos();
+            bins.iter_mut().for_each(|b| *b = 0);
+            for &(x, y) in &dark {
+                let row = (y * cos - x * sin).round() as isize + diagonal as isize;
+                if let Some(bin) = bins.get_mut(row as usize) {
+                    *bin += 1;
+                }
+            }
+            let score: u64 = bins.iter().map(|&b| (b as u64) * (b as u64)).sum();
+            if score > best.1 {
+                best = (angle, score);
+            }
+        }
+        best.0
+    }
+
+    /// 除以模糊后的背景去除阴影，再拉伸对比度
+    fn enhance(img: &DynamicImage) -> DynamicImage {
+        let gray = img.to_luma8();
+        let (width, height) = gray.dimensions();
+
+        // 在缩小的图上估算背景亮度，避免大半径模糊的开销
+        let small = imageops::resize(
+            &gray,
+            (width / 8).max(1),
+            (height / 8).max(1),
+            imageops::FilterType::Triangle,
+        );
+        let background = imageops::resize(
+            &gaussian_blur_f32(&small, 4.0),
+            width,
+            height,
+            imageops::FilterType::Triangle,
+        );
+        let flattened = GrayImage::from_fn(width, height, |x, y| {
+            let p = gray.get_pixel(x, y)[0] as f32;
+            let bg = background.get_pixel(x, y)[0].max(1) as f32;
+            Luma([(p / bg * 255.0).min(255.0) as u8])
+        });
+
+        let (low, high) = percentiles(&flattened, 0.01, 0.99);
+        if low < high {
+            DynamicImage::ImageLuma8(stretch_contrast(&flattened, low, high, 0, 255))
+        } else {
+            DynamicImage::ImageLuma8(flattened)
+        }
+    }
+
+    /// 缩小到分析尺寸的灰度图，返回缩放比例
+    fn analysis_image(img: &DynamicImage) -> (f32, GrayImage) {
+        let longest = img.width().max(img.height()).max(1);
+        let scale = (ANALYSIS_SIZE as f32 / longest as f32).min(1.0);
+        let small = if scale < 1.0 {
+            img.resize(
+                (img.width() as f32 * scale) as u32,
+                (img.height() as f32 * scale) as u32,
+                imageops::FilterType::Triangle,
+            )
+        } else {
+            img.clone()
+        };
+        (scale, small.to_luma8())
+    }
+}
+
+/// 按左上、右上、右下、左下排列四个角
+fn order_corners(points: [(f32, f32); 4]) -> [(f32, f32); 4] {
+    let by = |key: fn(&(f32, f32)) -> f32, max: bool| {
+        let iter = points.iter().copied();
+        if max {
+            iter.max_by(|a, b| key(a).total_cmp(&key(b)))
+        } else {
+            iter.min_by(|a, b| key(a).total_cmp(&key(b)))
+        }
+        .unwrap_or_default()
+    };
+    [
+        by(|p| p.0 + p.1, false),
+        by(|p| p.0 - p.1, true),
+        by(|p| p.0 + p.1, true),
+        by(|p| p.0 - p.1, false),
+    ]
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// 灰度直方图的上下分位点
+fn percentiles(gray: &GrayImage, lower: f32, upper: f32) -> (u8, u8) {
+    let mut histogram = [0u64; 256];
+    for p in gray.pixels() {
+        histogram[p[0] as usize] += 1;
+    }
+    let total = (gray.width() as u64 * gray.height() as u64) as f32;
+    let find = |fraction: f32| {
+        let target = (total * fraction) as u64;
+        let mut seen = 0;
+        histogram
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen > target
+            })
+            .unwrap_or(255) as u8
+    };
+    (find(lower), find(upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_skew_of_rotated_lines() {
+        let mut img = GrayImage::from_pixel(400, 300, Luma([255]));
+        for row in (40..260).step_by(30) {
+            for y in row..row + 4 {
+                for x in 40..360 {
+                    img.put_pixel(x, y, Luma([0]));
+                }
+            }
+        }
+        let skewed = rotate_about_center(
+            &img,
+            4f32.to_radians(),
+            Interpolation::Bilinear,
+            Luma([255]),
+        );
+
+        let angle = ImagePreprocessor::estimate_skew(&skewed);
+        assert!((angle - 4.0).abs() <= 0.5, "estimated {}", angle);
+    }
+}
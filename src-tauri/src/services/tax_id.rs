@@ -0,0 +1,201 @@
+//! 纳税人识别号校验：18 位统一社会信用代码（GB 32100），
+//! 以及旧版 15 位（地区码 + 组织机构代码）和 20 位（身份证号 + 2 位）税号
+
+/// 统一社会信用代码字符集（不含 I、O、Z、S、V），下标即字符的值
+const CREDIT_CODE_CHARS: &str = "0123456789ABCDEFGHJKLMNPQRTUWXY";
+/// 统一社会信用代码前 17 位的加权因子
+const CREDIT_CODE_WEIGHTS: [u32; 17] = [
+    1, 3, 9, 27, 19, 26, 16, 17, 20, 29, 25, 13, 8, 24, 10, 30, 28,
+];
+/// 组织机构代码（GB 11714）前 8 位的加权因子
+const ORG_CODE_WEIGHTS: [u32; 8] = [3, 7, 9, 10, 5, 8, 4, 2];
+/// 身份证号（GB 11643）前 17 位的加权因子
+const ID_CARD_WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+/// 身份证号校验码，下标为加权和除以 11 的余数
+const ID_CARD_CHECK_CHARS: &[u8; 11] = b"10X98765432";
+
+/// 统一社会信用代码中不会出现、可直接替换的 OCR 易错字符
+const UNAMBIGUOUS_FIXES: [(char, char); 5] =
+    [('O', '0'), ('I', '1'), ('Z', '2'), ('S', '5'), ('V', 'U')];
+/// OCR 常见的形近字符，生成修正建议时逐位尝试互换
+const CONFUSABLE: [(char, char); 10] = [
+    ('0', 'O'),
+    ('0', 'D'),
+    ('0', 'Q'),
+    ('1', 'I'),
+    ('1', 'L'),
+    ('2', 'Z'),
+    ('5', 'S'),
+    ('6', 'G'),
+    ('8', 'B'),
+    ('U', 'V'),
+];
+
+/// 税号校验结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaxIdCheck {
+    Valid,
+    /// 去除空白、统一大写或替换易错字符后可通过校验
+    Corrected(String),
+    /// 无法通过校验，附带替换单个形近字符后能通过校验的候选值
+    Invalid {
+        suggestions: Vec<String>,
+    },
+}
+
+pub struct TaxIdValidator;
+
+impl TaxIdValidator {
+    /// 校验税号，只有修正方式唯一时才返回 `Corrected`
+    pub fn check(value: &str) -> TaxIdCheck {
+        let normalized: String = value
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if Self::is_valid(&normalized) {
+            return if normalized == value {
+                TaxIdCheck::Valid
+            } else {
+                TaxIdCheck::Corrected(normalized)
+            };
+        }
+
+        if normalized.chars().count() == 18 {
+            let fixed: String = normalized
+                .chars()
+                .map(|c| {
+                    UNAMBIGUOUS_FIXES
+                        .iter()
+                        .find(|(from, _)| *from == c)
+                        .map_or(c, |(_, to)| *to)
+                })
+                .collect();
+            if Self::is_valid(&fixed) {
+                return TaxIdCheck::Corrected(fixed);
+            }
+        }
+
+        TaxIdCheck::Invalid {
+            suggestions: Self::suggestions(&normalized),
+        }
+    }
+
+    /// 是否为校验码正确的 18 位、15 位或 20 位税号
+    pub fn is_valid(value: &str) -> bool {
+        if !value.is_ascii() {
+            return false;
+        }
+        match value.len() {
+            18 => is_valid_credit_code(value),
+            15 => value[..6].bytes().all(|b| b.is_ascii_digit()) && is_valid_org_code(&value[6..]),
+            20 => is_valid_id_card(&value[..18]) && value[18..].bytes().all(|b| b.is_ascii_digit()),
+            _ => false,
+        }
+    }
+
+    /// 逐位替换一个形近字符，收集能通过校验的候选值
+    fn suggestions(value: &str) -> Vec<String> {
+        let chars: Vec<char> = value.chars().collect();
+        let mut suggestions = Vec::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let replacements = CONFUSABLE.iter().filter_map(|&(a, b)| match c {
+                _ if c == a => Some(b),
+                _ if c == b => Some(a),
+                _ => None,
+            });
+            for replacement in replacements {
+                let mut candidate = chars.clone();
+                candidate[i] = replacement;
+                let candidate: String = candidate.into_iter().collect();
+                if Self::is_valid(&candidate) && !suggestions.contains(&candidate) {
+                    suggestions.push(candidate);
+                }
+            }
+        }
+        suggestions
+    }
+}
+
+fn is_valid_credit_code(value: &str) -> bool {
+    let values: Option<Vec<u32>> = value
+        .chars()
+        .map(|c| CREDIT_CODE_CHARS.find(c).map(|i| i as u32))
+        .collect();
+    let Some(values) = values else {
+        return false;
+    };
+    let sum: u32 = values[..17]
+        .iter()
+        .zip(CREDIT_CODE_WEIGHTS)
+        .map(|(v, w)| v * w)
+        .sum();
+    (31 - sum % 31) % 31 == values[17]
+}
+
+fn is_valid_org_code(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 9 || !bytes[..8].iter().all(|b| b.is_ascii_alphanumeric()) {
+        return false;
+    }
+    let sum: u32 = bytes[..8]
+        .iter()
+        .zip(ORG_CODE_WEIGHTS)
+        .map(|(b, w)| (*b as char).to_digit(36).unwrap_or(0) * w)
+        .sum();
+    let expected = match 11 - sum % 11 {
+        10 => b'X',
+        11 => b'0',
+        check => b'0' + check as u8,
+    };
+    bytes[8] == expected
+}
+
+fn is_valid_id_card(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 18 || !bytes[..17].iter().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let sum: u32 = bytes[..17]
+        .iter()
+        .zip(ID_CARD_WEIGHTS)
+        .map(|(b, w)| (b - b'0') as u32 * w)
+        .sum();
+    bytes[17] == ID_CARD_CHECK_CHARS[(sum % 11) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_tax_ids() {
+        assert_eq!(
+            TaxIdValidator::check("91350100M000100Y43"),
+            TaxIdCheck::Valid
+        );
+        assert!(TaxIdValidator::is_valid("110105D2143569X"));
+        assert!(TaxIdValidator::is_valid("11010519491231002X01"));
+
+        // O 不在字符集中，直接替换为 0
+        assert_eq!(
+            TaxIdValidator::check("9135O1OOM000100Y43"),
+            TaxIdCheck::Corrected("91350100M000100Y43".to_string())
+        );
+        assert_eq!(
+            TaxIdValidator::check("91110108 551385082q"),
+            TaxIdCheck::Corrected("91110108551385082Q".to_string())
+        );
+        // B 与 8 都是合法字符，只能给出建议
+        match TaxIdValidator::check("9111010B551385082Q") {
+            TaxIdCheck::Invalid { suggestions } => {
+                assert!(suggestions.contains(&"91110108551385082Q".to_string()))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            TaxIdValidator::check("91110108551385O82Q"),
+            TaxIdCheck::Corrected("91110108551385082Q".to_string())
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! 发票校验：合计是否一致、金额正负、明细税率是否合法、税号校验码是否正确
+
+use crate::models::invoice::{Invoice, InvoiceType};
+use crate::models::money::Money;
+use crate::models::ocr_response::{parse_money, VatInvoiceResponse};
+use crate::models::warning::InvoiceWarning;
+use crate::services::tax_id::{TaxIdCheck, TaxIdValidator};
+
+/// 法定增值税税率（%），含已调整但仍可能出现在历史发票上的税率
+const LEGAL_TAX_RATES: [&str; 13] = [
+    "0", "0.5", "1", "1.5", "3", "5", "6", "9", "10", "11", "13", "16", "17",
+];
+/// 不以百分比表示的合法税率栏
+const NON_RATE_LABELS: [&str; 3] = ["免税", "不征税", "***"];
+
+/// 商品明细中与校验相关的字段
+struct InvoiceLine {
+    row: Option<String>,
+    amount: Option<Money>,
+    tax_rate: Option<String>,
+}
+
+pub struct InvoiceValidator;
+
+impl InvoiceValidator {
+    /// 校验发票金额和税号，增值税发票还会核对原始 OCR 结果中的商品明细
+    pub fn validate(invoice: &Invoice) -> Vec<InvoiceWarning> {
+        let mut warnings = Vec::new();
+
+        let amounts = [
+            ("amount_without_tax", invoice.amount_without_tax),
+            ("tax_amount", invoice.tax_amount),
+            ("total_amount", Some(invoice.total_amount)),
+        ];
+        for (field, amount) in amounts {
+            if let Some(amount) = amount.filter(|amount| *amount < Money::ZERO) {
+                warnings.push(InvoiceWarning::NegativeAmount {
+                    field: field.to_string(),
+                    amount,
+                });
+            }
+        }
+
+        // 机票行程单的合计还包含民航发展基金和保险费，不等于计税金额与税额之和
+        if invoice.total_amount == Money::ZERO {
+            warnings.push(InvoiceWarning::ZeroTotal);
+        } else if let (Some(amount_without_tax), Some(tax_amount), false) = (
+            invoice.amount_without_tax,
+            invoice.tax_amount,
+            invoice.invoice_type == InvoiceType::FlightItinerary,
+        ) {
+            if amount_without_tax + tax_amount != invoice.total_amount {
+                warnings.push(InvoiceWarning::AmountMismatch {
+                    amount_without_tax,
+                    tax_amount,
+                    total_amount: invoice.total_amount,
+                });
+            }
+        }
+
+        let lines = Self::vat_lines(invoice);
+        if let Some(expected) = invoice.amount_without_tax {
+            let line_amounts: Option<Vec<Money>> = lines.iter().map(|line| line.amount).collect();
+            if let Some(line_amounts) = line_amounts.filter(|amounts| !amounts.is_empty()) {
+                let line_sum: Money = line_amounts.into_iter().sum();
+                if line_sum != expected {
+                    warnings.push(InvoiceWarning::LineSumMismatch { line_sum, expected });
+                }
+            }
+        }
+
+        for (field, value) in Self::tax_numbers(invoice) {
+            let suggestions = match TaxIdValidator::check(value) {
+                TaxIdCheck::Valid => continue,
+                TaxIdCheck::Corrected(corrected) => vec![corrected],
+                TaxIdCheck::Invalid { suggestions } => suggestions,
+            };
+            warnings.push(InvoiceWarning::InvalidTaxNumber {
+                field: field.to_string(),
+                value: value.to_string(),
+                suggestions,
+            });
+        }
+
+        for line in &lines {
+            if let Some(rate) = line.tax_rate.as_deref() {
+                if !is_legal_tax_rate(rate) {
+                    warnings.push(InvoiceWarning::InvalidTaxRate {
+                        row: line.row.clone(),
+                        rate: rate.to_string(),
+                    });
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// 修正税号中可确定的 OCR 错误后重新校验，并更新 `invoice.warnings`
+    ///
+    /// 二维码比对、重复发票等其他来源的提示保留；之前的税号修正记录只在税号
+    /// 仍是修正结果且本次没有重新修正时保留。
+    pub fn apply(invoice: &mut Invoice) {
+        let corrections = Self::correct_tax_numbers(invoice);
+        let recorrected: Vec<&str> = corrections
+            .iter()
+            .filter_map(|warning| match warning {
+                InvoiceWarning::TaxNumberCorrected { field, .. } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        let tax_numbers = Self::tax_numbers(invoice);
+        let mut warnings: Vec<InvoiceWarning> =
+            InvoiceWarning::list_from_json(invoice.warnings.as_deref())
+                .into_iter()
+                .filter(|warning| match warning {
+                    InvoiceWarning::TaxNumberCorrected {
+                        field, corrected, ..
+                    } => {
+                        !recorrected.contains(&field.as_str())
+                            && tax_numbers.contains(&(field.as_str(), corrected.as_str()))
+                    }
+                    warning => !warning.is_validation(),
+                })
+                .collect();
+        warnings.extend(corrections);
+        warnings.extend(Self::validate(invoice));
+        invoice.warnings = InvoiceWarning::list_to_json(&warnings);
+    }
+
+    /// 自动修正买卖方税号，返回修正记录
+    fn correct_tax_numbers(invoice: &mut Invoice) -> Vec<InvoiceWarning> {
+        let mut warnings = Vec::new();
+        let fields = [
+            ("buyer_tax_number", &mut invoice.buyer_tax_number),
+            ("seller_tax_number", &mut invoice.seller_tax_number),
+        ];
+        for (field, value) in fields {
+            let Some(original) = value.as_deref().filter(|v| !v.trim().is_empty()) else {
+                continue;
+            };
+            if let TaxIdCheck::Corrected(corrected) = TaxIdValidator::check(original) {
+                warnings.push(InvoiceWarning::TaxNumberCorrected {
+                    field: field.to_string(),
+                    original: original.to_string(),
+                    corrected: corrected.clone(),
+                });
+                *value = Some(corrected);
+            }
+        }
+        warnings
+    }
+
+    /// 非空的买卖方税号
+    fn tax_numbers(invoice: &Invoice) -> Vec<(&'static str, &str)> {
+        [
+            ("buyer_tax_number", invoice.buyer_tax_number.as_deref()),
+            ("seller_tax_number", invoice.seller_tax_number.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| Some((field, value.filter(|v| !v.trim().is_empty())?)))
+        .collect()
+    }
+
+    /// 从增值税发票的原始 OCR 响应中取出商品明细，按行号对齐金额和税率
+    fn vat_lines(invoice: &Invoice) -> Vec<InvoiceLine> {
+        if !invoice.invoice_type.is_vat() {
+            return Vec::new();
+        }
+        let Some(response) = invoice
+            .ocr_raw_response
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<VatInvoiceResponse>(raw).ok())
+        else {
+            return Vec::new();
+        };
+
+        let wr = response.words_result;
+        let amounts = wr.commodity_amount.unwrap_or_default();
+        let rates = wr.commodity_tax_rate.unwrap_or_default();
+        let count = amounts.len().max(rates.len());
+        (0..count)
+            .map(|i| {
+                let amount = amounts.get(i);
+                let rate = rates.get(i);
+                InvoiceLine {
+                    row: amount.or(rate).and_then(|item| item.row.clone()),
+                    amount: amount.and_then(|item| parse_money(Some(&item.word))),
+                    tax_rate: rate
+                        .map(|item| item.word.trim().to_string())
+                        .filter(|rate| !rate.is_empty()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// 税率栏是否为法定税率，如 `13%`、`1.5%`、`免税`
+fn is_legal_tax_rate(rate: &str) -> bool {
+    if NON_RATE_LABELS.contains(&rate) {
+        return true;
+    }
+    let Some(percent) = rate.strip_suffix('%') else {
+        return false;
+    };
+    let percent = percent.trim();
+    let normalized = if percent.contains('.') {
+        percent.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        percent
+    };
+    LEGAL_TAX_RATES.contains(&normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_vat_amounts() {
+        let mut invoice = Invoice::new(InvoiceType::VatInvoice, Money::from_fen(113000));
+        invoice.amount_without_tax = Some(Money::from_fen(100000));
+        invoice.tax_amount = Some(Money::from_fen(12000));
+        invoice.ocr_raw_response = Some(
+            r#"{"words_result": {
+                "CommodityAmount": [{"word": "600.00", "row": "1"}, {"word": "300.00", "row": "2"}],
+                "CommodityTaxRate": [{"word": "13%", "row": "1"}, {"word": "12%", "row": "2"}]
+            }}"#
+            .to_string(),
+        );
+
+        assert_eq!(
+            InvoiceValidator::validate(&invoice),
+            vec![
+                InvoiceWarning::AmountMismatch {
+                    amount_without_tax: Money::from_fen(100000),
+                    tax_amount: Money::from_fen(12000),
+                    total_amount: Money::from_fen(113000),
+                },
+                InvoiceWarning::LineSumMismatch {
+                    line_sum: Money::from_fen(90000),
+                    expected: Money::from_fen(100000),
+                },
+                InvoiceWarning::InvalidTaxRate {
+                    row: Some("2".to_string()),
+                    rate: "12%".to_string(),
+                },
+            ]
+        );
+
+        // 修正后重新校验，其他来源的提示保留
+        invoice.tax_amount = Some(Money::from_fen(13000));
+        invoice.warnings = Some(r#"[{"kind": "zero_total"}, {"kind": "qr_mismatch", "field": "check_code", "ocr_value": "1", "qr_value": "2"}]"#.to_string());
+        invoice.ocr_raw_response = None;
+        InvoiceValidator::apply(&mut invoice);
+        let warnings = InvoiceWarning::list_from_json(invoice.warnings.as_deref());
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(warnings[0], InvoiceWarning::QrMismatch { .. }));
+
+        // 税号修正记录随重新校验更新，不会重复或残留
+        invoice.seller_tax_number = Some("9135O1OOM000100Y43".to_string());
+        InvoiceValidator::apply(&mut invoice);
+        InvoiceValidator::apply(&mut invoice);
+        let warnings = InvoiceWarning::list_from_json(invoice.warnings.as_deref());
+        assert_eq!(
+            invoice.seller_tax_number.as_deref(),
+            Some("91350100M000100Y43")
+        );
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[1],
+            InvoiceWarning::TaxNumberCorrected { .. }
+        ));
+
+        invoice.seller_tax_number = Some("91110108551385O82Q".to_string());
+        InvoiceValidator::apply(&mut invoice);
+        let warnings = InvoiceWarning::list_from_json(invoice.warnings.as_deref());
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            &warnings[1],
+            InvoiceWarning::TaxNumberCorrected { corrected, .. } if corrected == "91110108551385082Q"
+        ));
+
+        invoice.seller_tax_number = Some("91350100M000100Y43".to_string());
+        InvoiceValidator::apply(&mut invoice);
+        let warnings = InvoiceWarning::list_from_json(invoice.warnings.as_deref());
+        assert_eq!(warnings.len(), 1);
+
+        let mut flight = Invoice::new(InvoiceType::FlightItinerary, Money::from_fen(143800));
+        flight.amount_without_tax = Some(Money::from_fen(120000));
+        flight.tax_amount = Some(Money::from_fen(10800));
+        assert!(InvoiceValidator::validate(&flight).is_empty());
+
+        assert!(is_legal_tax_rate("1.50%"));
+        assert!(is_legal_tax_rate("0.5%"));
+        assert!(is_legal_tax_rate("免税"));
+        assert!(!is_legal_tax_rate("7%"));
+    }
+}
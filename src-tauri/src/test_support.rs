@@ -0,0 +1,136 @@
+//! 测试辅助：内存数据库与百度 OCR mock 服务
+
+use mockito::{Matcher, Mock, Server, ServerGuard};
+
+/// 录制的百度 token 响应
+pub const BAIDU_TOKEN_JSON: &str = include_str!("../tests/fixtures/baidu/token.json");
+/// 录制的增值税发票识别响应
+pub const BAIDU_VAT_INVOICE_JSON: &str = include_str!("../tests/fixtures/baidu/vat_invoice.json");
+/// 录制的火车票识别响应
+pub const BAIDU_TRAIN_TICKET_JSON: &str = include_str!("../tests/fixtures/baidu/train_ticket.json");
+
+/// 本地百度 OCR mock 服务，回放录制的响应
+pub struct MockBaiduServer {
+    server: ServerGuard,
+    mocks: Vec<Mock>,
+}
+
+impl MockBaiduServer {
+    /// 启动 mock 服务并注册 token 接口
+    pub async fn start() -> Self {
+        let mut server = Server::new_async().await;
+        let token = server
+            .mock("POST", "/oauth/2.0/token")
+            .match_query(Matcher::UrlEncoded(
+                "grant_type".into(),
+                "client_credentials".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(BAIDU_TOKEN_JSON)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        Self {
+            server,
+            mocks: vec![token],
+        }
+    }
+
+    /// 服务地址，用作 `baidu_ocr_base_url`
+    pub fn url(&self) -> String {
+        self.server.url()
+    }
+
+    /// 注册识别接口，`path` 形如 `/rest/2.0/ocr/v1/vat_invoice`
+    pub async fn replay(&mut self, path: &str, body: &str) -> &mut Self {
+        let mock = self
+            .server
+            .mock("POST", path)
+            .match_query(Matcher::Regex("access_token=.+".into()))
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        self.mocks.push(mock);
+        self
+    }
+
+    /// 注册识别接口，只响应表单参数 `name` 等于 `value` 的请求（如 PDF 页码）
+    pub async fn replay_param(
+        &mut self,
+        path: &str,
+        name: &str,
+        value: &str,
+        body: &str,
+    ) -> &mut Self {
+        let mock = self
+            .server
+            .mock("POST", path)
+            .match_query(Matcher::Regex("access_token=.+".into()))
+            .match_body(Matcher::UrlEncoded(name.into(), value.into()))
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        self.mocks.push(mock);
+        self
+    }
+
+    /// 断言所有已注册的接口都被调用过
+    pub fn assert_all_called(&self) {
+        for mock in &self.mocks {
+            mock.assert();
+        }
+    }
+}
+
+/// 在临时目录写入一张测试图片，返回文件路径
+pub fn write_test_image(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("tax-recognize-tests");
+    std::fs::create_dir_all(&dir).expect("创建临时目录失败");
+    let path = dir.join(format!("{}-{}.png", name, uuid::Uuid::new_v4()));
+    image::RgbImage::from_pixel(64, 48, image::Rgb([255, 255, 255]))
+        .save(&path)
+        .expect("写入测试图片失败");
+    path
+}
+
+/// 在临时目录写入一个指定页数的空白 PDF，返回文件路径
+pub fn write_test_pdf(name: &str, pages: u32) -> std::path::PathBuf {
+    use lopdf::{dictionary, Document, Object};
+
+    let dir = std::env::temp_dir().join("tax-recognize-tests");
+    std::fs::create_dir_all(&dir).expect("创建临时目录失败");
+    let path = dir.join(format!("{}-{}.pdf", name, uuid::Uuid::new_v4()));
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let kids: Vec<Object> = (0..pages)
+        .map(|_| {
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            })
+            .into()
+        })
+        .collect();
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => pages as i64,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(&path).expect("写入测试PDF失败");
+    path
+}
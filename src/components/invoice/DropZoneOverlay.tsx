@@ -12,7 +12,7 @@ export function DropZoneOverlay({ isDragOver }: DropZoneOverlayProps) {
       <div className="bg-white rounded-2xl shadow-2xl p-8 flex flex-col items-center border-2 border-dashed border-blue-500">
         <Upload className="h-16 w-16 text-blue-500 mb-4" />
         <p className="text-xl font-medium text-gray-800">释放文件开始识别</p>
-        <p className="text-sm text-gray-500 mt-1">支持 JPG、PNG、BMP、PDF 格式</p>
+        <p className="text-sm text-gray-500 mt-1">支持 JPG、PNG、BMP、TIFF、WebP、HEIC、PDF、OFD 格式</p>
       </div>
     </div>
   );
@@ -3,7 +3,7 @@ import { Upload } from 'lucide-react';
 import { open } from '@tauri-apps/plugin-dialog';
 import { getCurrentWebview } from '@tauri-apps/api/webview';
 import { toast } from 'sonner';
-import { invoiceService } from '../../services/invoiceService';
+import { invoiceService, RecognizeResult } from '../../services/invoiceService';
 import { useInvoiceStore } from '../../stores/invoiceStore';
 import { useSettingsStore } from '../../stores/settingsStore';
 import { useUIStore } from '../../stores/uiStore';
@@ -11,7 +11,9 @@ import { UploadQueue, UploadItem } from './UploadQueue';
 import { DropZoneOverlay } from './DropZoneOverlay';
 
 /** 支持的文件扩展名 */
-const ACCEPTED_EXTENSIONS = ['.jpg', '.jpeg', '.png', '.bmp', '.pdf'];
+const ACCEPTED_EXTENSIONS = [
+  '.jpg', '.jpeg', '.png', '.bmp', '.tif', '.tiff', '.webp', '.heic', '.heif', '.pdf', '.ofd',
+];
 
 /** 生成唯一ID */
 function generateId(): string {
@@ -27,6 +29,21 @@ const RETRY_DELAY = 500;
 /** 最大重试次数 */
 const MAX_RETRIES = 3;
 
+/** 没有发票被保存时，说明原因（重复、购买方不符可能同时存在） */
+function describeUnsaved(result: RecognizeResult): string | undefined {
+  if (result.invoices.length > 0) return undefined;
+  const reasons: string[] = [];
+  if (result.duplicates.length > 0) {
+    const ids = result.duplicates.map((d) => d.existingId).join(', ');
+    reasons.push(`发票已存在（ID: ${ids}）`);
+  }
+  if (result.rejectedBuyers.length > 0) {
+    const buyers = result.rejectedBuyers.map((b) => b.buyerName ?? '未知').join(', ');
+    reasons.push(`购买方不是本公司（${buyers}）`);
+  }
+  return reasons.length > 0 ? `${reasons.join('；')}，未保存` : undefined;
+}
+
 /** 判断是否为可重试错误（QPS限制、网络错误等） */
 function isRetryableError(error: unknown): boolean {
   const msg = error instanceof Error ? error.message : String(error);
@@ -96,7 +113,16 @@ export function FileUploader() {
     let lastError: unknown;
     for (let attempt = 0; attempt <= MAX_RETRIES; attempt++) {
       try {
-        await invoiceService.recognizeAndSaveInvoice(item.path);
+        const result = await invoiceService.recognizeAndSaveInvoice(item.path);
+        const unsaved = describeUnsaved(result);
+        if (unsaved) {
+          setUploadItems((prev) =>
+            prev.map((i) =>
+              i.id === item.id ? { ...i, status: 'error', error: unsaved } : i
+            )
+          );
+          return false;
+        }
         setUploadItems((prev) =>
           prev.map((i) => (i.id === item.id ? { ...i, status: 'success' } : i))
         );
@@ -218,7 +244,7 @@ export function FileUploader() {
             
             const invalidCount = paths.length - validPaths.length;
             if (invalidCount > 0) {
-              toast.error(`${invalidCount} 个文件格式不支持，请上传 JPG、PNG、BMP 或 PDF 文件`);
+              toast.error(`${invalidCount} 个文件格式不支持，请上传 JPG、PNG、BMP、TIFF、WebP、HEIC、PDF 或 OFD 文件`);
             }
             
             if (newPaths.length > 0) {
@@ -256,7 +282,7 @@ export function FileUploader() {
         filters: [
           {
             name: '发票文件',
-            extensions: ['jpg', 'jpeg', 'png', 'bmp', 'pdf'],
+            extensions: ['jpg', 'jpeg', 'png', 'bmp', 'tif', 'tiff', 'webp', 'heic', 'heif', 'pdf', 'ofd'],
           },
         ],
       });
@@ -1,14 +1,19 @@
 import { useEffect, useState, useCallback } from 'react';
-import { Save, CheckCircle } from 'lucide-react';
+import { Save, CheckCircle, AlertTriangle } from 'lucide-react';
 import { toast } from 'sonner';
 import { Modal } from '../ui/Modal';
 import { ConfirmModal } from '../ui/ConfirmModal';
 import { Button } from '../ui/Button';
 import { Input } from '../ui/Input';
 import { Loading } from '../ui/Loading';
-import { Invoice, InvoiceTypeLabels } from '../../types/invoice';
+import {
+  BuyerCheckLabels,
+  Invoice,
+  InvoiceTypeLabels,
+  parseInvoiceWarnings,
+} from '../../types/invoice';
 import { invoiceService } from '../../services/invoiceService';
-import { formatAmount, formatDate, formatTaxNumber } from '../../utils/format';
+import { formatAmount, formatDate, formatTaxNumber, formatWarning } from '../../utils/format';
 
 interface InvoiceDetailProps {
   invoiceId: string | null;
@@ -117,6 +122,8 @@ export function InvoiceDetail({
     onClose();
   }, [onClose]);
 
+  const warnings = parseInvoiceWarnings(invoice?.warnings);
+
   const footer = (
     <>
       <Button variant="ghost" onClick={handleClose}>
@@ -144,6 +151,18 @@ export function InvoiceDetail({
         </div>
       ) : invoice ? (
         <div className="space-y-6">
+          {/* 识别提示 */}
+          {warnings.length > 0 && (
+            <div className="px-4 py-3 border border-amber-200 bg-amber-50 rounded-lg space-y-1">
+              {warnings.map((warning, index) => (
+                <p key={index} className="flex items-start text-sm text-amber-700">
+                  <AlertTriangle className="h-4 w-4 mr-1.5 mt-0.5 flex-shrink-0" />
+                  {formatWarning(warning)}
+                </p>
+              ))}
+            </div>
+          )}
+
           {/* 基本信息 */}
           <DetailSection title="基本信息">
             <DetailRow label="发票类型">
@@ -182,6 +201,17 @@ export function InvoiceDetail({
             <DetailRow label="买方税号">
               {formatTaxNumber(invoice.buyerTaxNumber)}
             </DetailRow>
+            {invoice.buyerCheck && (
+              <DetailRow label="购买方核对">
+                <span
+                  className={
+                    invoice.buyerCheck === 'matched' ? 'text-green-600' : 'text-amber-600'
+                  }
+                >
+                  {BuyerCheckLabels[invoice.buyerCheck]}
+                </span>
+              </DetailRow>
+            )}
           </DetailSection>
 
           {/* 商品信息 */}
@@ -2,7 +2,13 @@ import { useState, useCallback } from 'react';
 import { Search, X, Filter, Trash2, Download } from 'lucide-react';
 import { save } from '@tauri-apps/plugin-dialog';
 import { toast } from 'sonner';
-import { InvoiceType, InvoiceTypeLabels, InvoiceFilter as FilterType } from '../../types/invoice';
+import {
+  BuyerCheck,
+  BuyerCheckLabels,
+  InvoiceType,
+  InvoiceTypeLabels,
+  InvoiceFilter as FilterType,
+} from '../../types/invoice';
 import { Button } from '../ui/Button';
 import { exportService } from '../../services/exportService';
 
@@ -58,6 +64,17 @@ export function InvoiceFilter({
     [onFilterChange]
   );
 
+  /** 处理购买方核对结果筛选 */
+  const handleBuyerCheckChange = useCallback(
+    (e: React.ChangeEvent<HTMLSelectElement>) => {
+      const value = e.target.value;
+      onFilterChange({
+        buyerCheck: value ? (value as BuyerCheck) : undefined,
+      });
+    },
+    [onFilterChange]
+  );
+
   /** 处理日期范围变更 */
   const handleDateFromChange = useCallback(
     (e: React.ChangeEvent<HTMLInputElement>) => {
@@ -128,7 +145,8 @@ export function InvoiceFilter({
     filter.invoiceType ||
     filter.dateFrom ||
     filter.dateTo ||
-    filter.keyword;
+    filter.keyword ||
+    filter.buyerCheck;
 
   return (
     <div className="bg-white rounded-lg border border-gray-200 p-4">
@@ -172,6 +190,20 @@ export function InvoiceFilter({
           </select>
         </div>
 
+        {/* 购买方筛选 */}
+        <select
+          value={filter.buyerCheck || ''}
+          onChange={handleBuyerCheckChange}
+          className="text-sm border border-gray-300 rounded-lg py-2 px-3 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-blue-500"
+        >
+          <option value="">全部购买方</option>
+          {Object.entries(BuyerCheckLabels).map(([check, label]) => (
+            <option key={check} value={check}>
+              {label}
+            </option>
+          ))}
+        </select>
+
         {/* 日期范围筛选 */}
         <div className="flex items-center gap-2">
           <input
@@ -1,5 +1,5 @@
 import { useEffect, useState, useCallback } from 'react';
-import { Save, TestTube, FolderOpen, CheckCircle, XCircle } from 'lucide-react';
+import { Save, TestTube, FolderOpen, CheckCircle, XCircle, Plus, Trash2 } from 'lucide-react';
 import { toast } from 'sonner';
 import { open } from '@tauri-apps/plugin-dialog';
 import { useSettingsStore } from '../stores/settingsStore';
@@ -7,6 +7,7 @@ import { invoiceService } from '../services/invoiceService';
 import { Button } from '../components/ui/Button';
 import { Input } from '../components/ui/Input';
 import { Loading } from '../components/ui/Loading';
+import { BuyerPolicy, CompanyEntity } from '../types/config';
 
 /**
  * 设置页面
@@ -16,12 +17,15 @@ export function SettingsPage() {
     apiKey,
     secretKey,
     exportPath,
+    companyEntities,
+    buyerPolicy,
     loaded,
     loading,
     error,
     loadSettings,
     saveApiConfig,
     saveExportPath,
+    saveCompanyConfig,
     clearError,
   } = useSettingsStore();
 
@@ -29,6 +33,8 @@ export function SettingsPage() {
   const [formApiKey, setFormApiKey] = useState('');
   const [formSecretKey, setFormSecretKey] = useState('');
   const [formExportPath, setFormExportPath] = useState('');
+  const [formEntities, setFormEntities] = useState<CompanyEntity[]>([]);
+  const [formBuyerPolicy, setFormBuyerPolicy] = useState<BuyerPolicy>('warn');
   const [testResult, setTestResult] = useState<boolean | null>(null);
   const [testing, setTesting] = useState(false);
 
@@ -43,8 +49,10 @@ export function SettingsPage() {
       setFormApiKey(apiKey);
       setFormSecretKey(secretKey);
       setFormExportPath(exportPath);
+      setFormEntities(companyEntities);
+      setFormBuyerPolicy(buyerPolicy);
     }
-  }, [loaded, apiKey, secretKey, exportPath]);
+  }, [loaded, apiKey, secretKey, exportPath, companyEntities, buyerPolicy]);
 
   // 显示错误
   useEffect(() => {
@@ -123,6 +131,31 @@ export function SettingsPage() {
     }
   }, [formExportPath, saveExportPath]);
 
+  /** 修改公司主体的某个字段 */
+  const handleEntityChange = useCallback(
+    (index: number, field: keyof CompanyEntity, value: string) => {
+      setFormEntities((entities) =>
+        entities.map((entity, i) => (i === index ? { ...entity, [field]: value } : entity))
+      );
+    },
+    []
+  );
+
+  /** 保存公司主体 */
+  const handleSaveCompanyConfig = useCallback(async () => {
+    const entities = formEntities
+      .map((entity) => ({ name: entity.name.trim(), tax_number: entity.tax_number.trim() }))
+      .filter((entity) => entity.name || entity.tax_number);
+    try {
+      const changed = await saveCompanyConfig(entities, formBuyerPolicy);
+      setFormEntities(entities);
+      toast.success(`公司主体保存成功，${changed} 张发票的核对结果已更新`);
+    } catch (err) {
+      const message = err instanceof Error ? err.message : '保存失败';
+      toast.error(message);
+    }
+  }, [formEntities, formBuyerPolicy, saveCompanyConfig]);
+
   if (!loaded) {
     return (
       <div className="flex items-center justify-center h-full">
@@ -225,6 +258,71 @@ export function SettingsPage() {
         </div>
       </section>
 
+      {/* 公司主体 */}
+      <section className="bg-white rounded-lg shadow-sm border border-gray-200 p-6">
+        <h2 className="text-lg font-semibold text-gray-800 mb-2">公司主体</h2>
+        <p className="text-sm text-gray-500 mb-4">
+          发票购买方须为以下主体之一，留空则不核对
+        </p>
+        <div className="space-y-3">
+          {formEntities.map((entity, index) => (
+            <div key={index} className="flex items-end gap-3">
+              <div className="flex-1">
+                <Input
+                  label={index === 0 ? '名称' : undefined}
+                  value={entity.name}
+                  onChange={(value) => handleEntityChange(index, 'name', value)}
+                  placeholder="公司名称"
+                />
+              </div>
+              <div className="flex-1">
+                <Input
+                  label={index === 0 ? '税号' : undefined}
+                  value={entity.tax_number}
+                  onChange={(value) => handleEntityChange(index, 'tax_number', value)}
+                  placeholder="统一社会信用代码"
+                />
+              </div>
+              <Button
+                variant="ghost"
+                onClick={() =>
+                  setFormEntities((entities) => entities.filter((_, i) => i !== index))
+                }
+              >
+                <Trash2 className="h-4 w-4" />
+              </Button>
+            </div>
+          ))}
+          <Button
+            variant="secondary"
+            size="sm"
+            onClick={() =>
+              setFormEntities((entities) => [...entities, { name: '', tax_number: '' }])
+            }
+          >
+            <Plus className="h-4 w-4 mr-1" />
+            添加主体
+          </Button>
+          <div>
+            <label className="block mb-1.5 text-sm font-medium text-gray-700">
+              购买方不符时
+            </label>
+            <select
+              value={formBuyerPolicy}
+              onChange={(e) => setFormBuyerPolicy(e.target.value as BuyerPolicy)}
+              className="text-sm border border-gray-300 rounded-lg py-2 px-3 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-blue-500"
+            >
+              <option value="warn">保存并提示</option>
+              <option value="reject">不保存</option>
+            </select>
+          </div>
+          <Button onClick={handleSaveCompanyConfig} loading={loading}>
+            <Save className="h-4 w-4 mr-1.5" />
+            保存主体
+          </Button>
+        </div>
+      </section>
+
       {/* 关于 */}
       <section className="bg-white rounded-lg shadow-sm border border-gray-200 p-6">
         <h2 className="text-lg font-semibold text-gray-800 mb-2">关于</h2>
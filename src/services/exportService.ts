@@ -34,6 +34,7 @@ export const exportService = {
       dateTo: filter.dateTo,
       keyword: filter.keyword,
       category: filter.category,
+      buyerCheck: filter.buyerCheck,
     });
   },
 };
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { FileInfo } from '../types/api';
+import { AttachmentContent, FileInfo } from '../types/api';
 
 /**
  * 文件服务 - 封装文件相关的 Tauri 命令调用
@@ -20,4 +20,12 @@ export const fileService = {
   async readFileAsBase64(filePath: string): Promise<string> {
     return invoke<string>('get_file_base64', { filePath });
   },
+
+  /**
+   * 读取归档的原始文件
+   * @param hash 附件哈希（Invoice.attachmentHash）
+   */
+  async getAttachment(hash: string): Promise<AttachmentContent> {
+    return invoke<AttachmentContent>('get_attachment', { hash });
+  },
 };
@@ -1,5 +1,5 @@
 export { configService } from './configService';
 export { invoiceService } from './invoiceService';
-export type { RecognizeResult } from './invoiceService';
+export type { DuplicateMatch, RecognizeResult, RejectedBuyer } from './invoiceService';
 export { exportService } from './exportService';
 export { fileService } from './fileService';
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { Invoice, InvoiceFilter, PagedResult } from '../types/invoice';
+import { BuyerCheck, Invoice, InvoiceFilter, PagedResult } from '../types/invoice';
 
 /**
  * 识别结果
@@ -7,10 +7,45 @@ import { Invoice, InvoiceFilter, PagedResult } from '../types/invoice';
 export interface RecognizeResult {
   filePath: string;
   success: boolean;
-  invoice?: Invoice;
+  invoices: Invoice[];
+  /** 与已有记录重复的发票 */
+  duplicates: DuplicateMatch[];
+  /** 购买方不是本公司、按配置未保存的发票 */
+  rejectedBuyers: RejectedBuyer[];
   error?: string;
 }
 
+/**
+ * 因购买方不是本公司而未保存的发票
+ */
+export interface RejectedBuyer {
+  invoiceNumber?: string;
+  buyerName?: string;
+  buyerTaxNumber?: string;
+}
+
+/**
+ * 重复发票检测结果
+ */
+export interface DuplicateMatch {
+  existingId: string;
+  /** 判定依据：code_number / file_hash / fuzzy */
+  reason: 'code_number' | 'file_hash' | 'fuzzy';
+  /** 处理方式：reject / warn / merge */
+  policy: 'reject' | 'warn' | 'merge';
+  invoiceNumber?: string;
+}
+
+/**
+ * 图片预处理选项（snake_case 与后端对应）
+ */
+export interface PreprocessOptions {
+  auto_rotate?: boolean;
+  deskew?: boolean;
+  crop?: boolean;
+  enhance?: boolean;
+}
+
 /**
  * 后端返回的分页结果（snake_case）
  */
@@ -40,12 +75,21 @@ interface BackendInvoice {
   seller_tax_number?: string;
   commodity_name?: string;
   commodity_detail?: string;
+  extra_data?: string;
   check_code?: string;
   machine_code?: string;
   original_file_path?: string;
+  processed_file_path?: string;
+  attachment_hash?: string;
+  page_number?: number;
+  bounding_box?: string;
   file_type?: string;
   ocr_raw_response?: string;
   ocr_confidence?: number;
+  classified_by?: string;
+  duplicate_of?: string;
+  buyer_check?: BuyerCheck;
+  warnings?: string;
   category?: string;
   remark?: string;
   is_verified: boolean;
@@ -72,12 +116,21 @@ function transformInvoice(backend: BackendInvoice): Invoice {
     sellerTaxNumber: backend.seller_tax_number,
     commodityName: backend.commodity_name,
     commodityDetail: backend.commodity_detail,
+    extraData: backend.extra_data,
     checkCode: backend.check_code,
     machineCode: backend.machine_code,
     originalFilePath: backend.original_file_path,
+    processedFilePath: backend.processed_file_path,
+    attachmentHash: backend.attachment_hash,
+    pageNumber: backend.page_number,
+    boundingBox: backend.bounding_box,
     fileType: backend.file_type,
     ocrRawResponse: backend.ocr_raw_response,
     ocrConfidence: backend.ocr_confidence,
+    classifiedBy: backend.classified_by,
+    duplicateOf: backend.duplicate_of,
+    buyerCheck: backend.buyer_check,
+    warnings: backend.warnings,
     category: backend.category,
     remark: backend.remark,
     isVerified: backend.is_verified,
@@ -86,6 +139,50 @@ function transformInvoice(backend: BackendInvoice): Invoice {
   };
 }
 
+/**
+ * 后端识别结果格式（snake_case）
+ */
+interface BackendRecognizeResult {
+  file_path: string;
+  success: boolean;
+  invoices: BackendInvoice[];
+  duplicates: {
+    existing_id: string;
+    reason: DuplicateMatch['reason'];
+    policy: DuplicateMatch['policy'];
+    invoice_number?: string;
+  }[];
+  rejected_buyers: {
+    invoice_number?: string;
+    buyer_name?: string;
+    buyer_tax_number?: string;
+  }[];
+  error?: string;
+}
+
+/**
+ * 将后端识别结果转换为前端格式
+ */
+function transformRecognizeResult(r: BackendRecognizeResult): RecognizeResult {
+  return {
+    filePath: r.file_path,
+    success: r.success,
+    invoices: r.invoices.map(transformInvoice),
+    duplicates: r.duplicates.map((d) => ({
+      existingId: d.existing_id,
+      reason: d.reason,
+      policy: d.policy,
+      invoiceNumber: d.invoice_number,
+    })),
+    rejectedBuyers: r.rejected_buyers.map((b) => ({
+      invoiceNumber: b.invoice_number,
+      buyerName: b.buyer_name,
+      buyerTaxNumber: b.buyer_tax_number,
+    })),
+    error: r.error,
+  };
+}
+
 /**
  * 将前端发票数据转换为后端格式
  */
@@ -105,12 +202,21 @@ function transformInvoiceToBackend(frontend: Invoice): BackendInvoice {
     seller_tax_number: frontend.sellerTaxNumber,
     commodity_name: frontend.commodityName,
     commodity_detail: frontend.commodityDetail,
+    extra_data: frontend.extraData,
     check_code: frontend.checkCode,
     machine_code: frontend.machineCode,
     original_file_path: frontend.originalFilePath,
+    processed_file_path: frontend.processedFilePath,
+    attachment_hash: frontend.attachmentHash,
+    page_number: frontend.pageNumber,
+    bounding_box: frontend.boundingBox,
     file_type: frontend.fileType,
     ocr_raw_response: frontend.ocrRawResponse,
     ocr_confidence: frontend.ocrConfidence,
+    classified_by: frontend.classifiedBy,
+    duplicate_of: frontend.duplicateOf,
+    buyer_check: frontend.buyerCheck,
+    warnings: frontend.warnings,
     category: frontend.category,
     remark: frontend.remark,
     is_verified: frontend.isVerified,
@@ -127,43 +233,44 @@ export const invoiceService = {
    * 识别并保存发票
    * @param filePath 文件路径
    * @param invoiceType 发票类型（可选，自动识别时不传）
+   * @param splitMultiple 一图多票时是否拆分为多张发票
+   * @param preprocess 图片预处理选项（可选）
    */
   async recognizeAndSaveInvoice(
     filePath: string,
-    invoiceType?: string
-  ): Promise<Invoice> {
-    const result = await invoke<BackendInvoice>('recognize_and_save_invoice', {
+    invoiceType?: string,
+    splitMultiple?: boolean,
+    preprocess?: PreprocessOptions
+  ): Promise<RecognizeResult> {
+    const result = await invoke<BackendRecognizeResult>('recognize_and_save_invoice', {
       filePath,
       invoiceType,
+      splitMultiple,
+      preprocess,
     });
-    return transformInvoice(result);
+    return transformRecognizeResult(result);
   },
 
   /**
    * 批量识别发票
    * @param filePaths 文件路径列表
    * @param invoiceType 发票类型（可选）
+   * @param splitMultiple 一图多票时是否拆分为多张发票
+   * @param preprocess 图片预处理选项（可选）
    */
   async recognizeInvoicesBatch(
     filePaths: string[],
-    invoiceType?: string
+    invoiceType?: string,
+    splitMultiple?: boolean,
+    preprocess?: PreprocessOptions
   ): Promise<RecognizeResult[]> {
-    interface BackendRecognizeResult {
-      file_path: string;
-      success: boolean;
-      invoice?: BackendInvoice;
-      error?: string;
-    }
     const results = await invoke<BackendRecognizeResult[]>('recognize_invoices_batch', {
       filePaths,
       invoiceType,
+      splitMultiple,
+      preprocess,
     });
-    return results.map((r) => ({
-      filePath: r.file_path,
-      success: r.success,
-      invoice: r.invoice ? transformInvoice(r.invoice) : undefined,
-      error: r.error,
-    }));
+    return results.map(transformRecognizeResult);
   },
 
   /**
@@ -184,6 +291,7 @@ export const invoiceService = {
       dateFrom: filter.dateFrom,
       dateTo: filter.dateTo,
       keyword: filter.keyword,
+      buyerCheck: filter.buyerCheck,
     });
     return {
       data: result.items.map(transformInvoice),
@@ -228,6 +336,14 @@ export const invoiceService = {
     return invoke('delete_invoices', { ids });
   },
 
+  /**
+   * 修改公司主体后重新核对全部发票的购买方
+   * @returns 核对结果有变化的发票数量
+   */
+  async recheckBuyers(): Promise<number> {
+    return invoke<number>('recheck_buyers');
+  },
+
   /**
    * 测试 OCR 连接
    * @param apiKey API Key
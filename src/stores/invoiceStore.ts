@@ -13,7 +13,7 @@ interface InvoiceState {
 
 interface InvoiceActions {
   fetchInvoices: () => Promise<void>;
-  addInvoice: (filePath: string) => Promise<Invoice | null>;
+  addInvoice: (filePath: string) => Promise<Invoice[]>;
   deleteInvoice: (id: string) => Promise<void>;
   deleteBatch: (ids: string[]) => Promise<void>;
   updateFilter: (filter: Partial<InvoiceFilter>) => void;
@@ -72,14 +72,14 @@ export const useInvoiceStore = create<InvoiceStore>((set, get) => ({
     set({ loading: true, error: null });
 
     try {
-      const invoice = await invoiceService.recognizeAndSaveInvoice(filePath);
+      const result = await invoiceService.recognizeAndSaveInvoice(filePath);
       // 重新获取列表以保持一致性
       await get().fetchInvoices();
-      return invoice;
+      return result.invoices;
     } catch (err) {
       const message = err instanceof Error ? err.message : '添加发票失败';
       set({ error: message, loading: false });
-      return null;
+      return [];
     }
   },
 
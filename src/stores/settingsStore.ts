@@ -1,12 +1,15 @@
 import { create } from 'zustand';
 import { configService } from '../services/configService';
-import { ConfigKeys } from '../types/config';
+import { invoiceService } from '../services/invoiceService';
+import { BuyerPolicy, CompanyEntity, ConfigKeys } from '../types/config';
 
 interface SettingsState {
   apiKey: string;
   secretKey: string;
   accessToken: string | null;
   exportPath: string;
+  companyEntities: CompanyEntity[];
+  buyerPolicy: BuyerPolicy;
   loaded: boolean;
   loading: boolean;
   error: string | null;
@@ -18,6 +21,7 @@ interface SettingsActions {
   testConnection: () => Promise<boolean>;
   setExportPath: (path: string) => void;
   saveExportPath: (path: string) => Promise<void>;
+  saveCompanyConfig: (entities: CompanyEntity[], policy: BuyerPolicy) => Promise<number>;
   clearError: () => void;
 }
 
@@ -29,6 +33,8 @@ export const useSettingsStore = create<SettingsStore>((set, get) => ({
   secretKey: '',
   accessToken: null,
   exportPath: '',
+  companyEntities: [],
+  buyerPolicy: 'warn',
   loaded: false,
   loading: false,
   error: null,
@@ -40,18 +46,23 @@ export const useSettingsStore = create<SettingsStore>((set, get) => ({
     set({ loading: true, error: null });
 
     try {
-      const [apiKey, secretKey, accessToken, exportPath] = await Promise.all([
-        configService.getConfig(ConfigKeys.BAIDU_OCR_API_KEY),
-        configService.getConfig(ConfigKeys.BAIDU_OCR_SECRET_KEY),
-        configService.getConfig(ConfigKeys.BAIDU_OCR_ACCESS_TOKEN),
-        configService.getConfig(ConfigKeys.EXPORT_DEFAULT_PATH),
-      ]);
+      const [apiKey, secretKey, accessToken, exportPath, companyEntities, buyerPolicy] =
+        await Promise.all([
+          configService.getConfig(ConfigKeys.BAIDU_OCR_API_KEY),
+          configService.getConfig(ConfigKeys.BAIDU_OCR_SECRET_KEY),
+          configService.getConfig(ConfigKeys.BAIDU_OCR_ACCESS_TOKEN),
+          configService.getConfig(ConfigKeys.EXPORT_DEFAULT_PATH),
+          configService.getConfig(ConfigKeys.COMPANY_ENTITIES),
+          configService.getConfig(ConfigKeys.BUYER_POLICY),
+        ]);
 
       set({
         apiKey: apiKey || '',
         secretKey: secretKey || '',
         accessToken: accessToken || null,
         exportPath: exportPath || '',
+        companyEntities: companyEntities ? JSON.parse(companyEntities) : [],
+        buyerPolicy: buyerPolicy === 'reject' ? 'reject' : 'warn',
         loaded: true,
         loading: false,
       });
@@ -138,6 +149,33 @@ export const useSettingsStore = create<SettingsStore>((set, get) => ({
     }
   },
 
+  saveCompanyConfig: async (entities: CompanyEntity[], policy: BuyerPolicy) => {
+    set({ loading: true, error: null });
+
+    try {
+      await Promise.all([
+        configService.setConfig(
+          ConfigKeys.COMPANY_ENTITIES,
+          JSON.stringify(entities),
+          '本公司主体'
+        ),
+        configService.setConfig(
+          ConfigKeys.BUYER_POLICY,
+          policy,
+          '购买方不是本公司时的处理方式'
+        ),
+      ]);
+      // 主体变化后已有发票的核对结果需要刷新
+      const changed = await invoiceService.recheckBuyers();
+      set({ companyEntities: entities, buyerPolicy: policy, loading: false });
+      return changed;
+    } catch (err) {
+      const message = err instanceof Error ? err.message : '保存公司主体失败';
+      set({ error: message, loading: false });
+      throw err;
+    }
+  },
+
   clearError: () => {
     set({ error: null });
   },
@@ -17,6 +17,19 @@ export interface FileInfo {
   fileType: FileType;
 }
 
+/**
+ * 归档的原始文件（字段与后端 snake_case 对应）
+ */
+export interface AttachmentContent {
+  hash: string;
+  size: number;
+  mime_type: string;
+  file_name?: string;
+  created_at: string;
+  /** 文件内容 base64 */
+  data: string;
+}
+
 /**
  * 文件类型
  */
@@ -38,16 +38,34 @@ export interface ExportColumn {
   width?: number;
 }
 
+/**
+ * 本公司主体（snake_case 与后端配置 JSON 对应）
+ */
+export interface CompanyEntity {
+  name: string;
+  tax_number: string;
+}
+
+/**
+ * 购买方不是本公司时的处理方式
+ */
+export type BuyerPolicy = 'warn' | 'reject';
+
 /**
  * 预定义配置键
  */
 export const ConfigKeys = {
+  OCR_PROVIDER: 'ocr_provider',
   BAIDU_OCR_API_KEY: 'baidu_ocr_api_key',
   BAIDU_OCR_SECRET_KEY: 'baidu_ocr_secret_key',
+  BAIDU_OCR_BASE_URL: 'baidu_ocr_base_url',
   BAIDU_OCR_ACCESS_TOKEN: 'baidu_ocr_access_token',
   BAIDU_OCR_TOKEN_EXPIRES: 'baidu_ocr_token_expires',
   EXPORT_DEFAULT_PATH: 'export_default_path',
   EXPORT_TEMPLATE: 'export_template',
+  DUPLICATE_POLICY: 'duplicate_policy',
+  COMPANY_ENTITIES: 'company_entities',
+  BUYER_POLICY: 'buyer_policy',
 } as const;
 
 export type ConfigKey = (typeof ConfigKeys)[keyof typeof ConfigKeys];
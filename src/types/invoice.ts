@@ -30,6 +30,20 @@ export const InvoiceTypeLabels: Record<InvoiceType, string> = {
   [InvoiceType.Other]: '其他',
 };
 
+/**
+ * 购买方核对结果（未配置公司主体时为空）
+ */
+export type BuyerCheck = 'matched' | 'mismatch' | 'missing';
+
+/**
+ * 购买方核对结果显示名称
+ */
+export const BuyerCheckLabels: Record<BuyerCheck, string> = {
+  matched: '本公司',
+  mismatch: '非本公司',
+  missing: '无购买方',
+};
+
 /**
  * 发票数据模型
  */
@@ -48,12 +62,21 @@ export interface Invoice {
   sellerTaxNumber?: string;
   commodityName?: string;
   commodityDetail?: string;
+  extraData?: string;
   checkCode?: string;
   machineCode?: string;
   originalFilePath?: string;
+  processedFilePath?: string;
+  attachmentHash?: string;
+  pageNumber?: number;
+  boundingBox?: string;
   fileType?: string;
   ocrRawResponse?: string;
   ocrConfidence?: number;
+  classifiedBy?: string;
+  duplicateOf?: string;
+  buyerCheck?: BuyerCheck;
+  warnings?: string;
   category?: string;
   remark?: string;
   isVerified: boolean;
@@ -61,6 +84,43 @@ export interface Invoice {
   updatedAt: string;
 }
 
+/**
+ * 识别提示（后端序列化在 Invoice.warnings 中，金额单位为元）
+ */
+export type InvoiceWarning =
+  | { kind: 'qr_mismatch'; field: string; ocr_value: string; qr_value: string }
+  | { kind: 'duplicate'; existing_id: string; reason: string }
+  | {
+      kind: 'amount_mismatch';
+      amount_without_tax: number;
+      tax_amount: number;
+      total_amount: number;
+    }
+  | { kind: 'line_sum_mismatch'; line_sum: number; expected: number }
+  | { kind: 'negative_amount'; field: string; amount: number }
+  | { kind: 'zero_total' }
+  | { kind: 'invalid_tax_rate'; row?: string; rate: string }
+  | { kind: 'invalid_date'; value: string }
+  | { kind: 'buyer_mismatch'; buyer_name?: string; buyer_tax_number?: string }
+  | { kind: 'invalid_tax_number'; field: string; value: string; suggestions: string[] }
+  | { kind: 'tax_number_corrected'; field: string; original: string; corrected: string }
+  | { kind: 'classify_fallback'; error: string };
+
+/**
+ * 解析发票的提示列表，格式错误时返回空列表
+ */
+export function parseInvoiceWarnings(warnings?: string): InvoiceWarning[] {
+  if (!warnings) {
+    return [];
+  }
+  try {
+    const parsed = JSON.parse(warnings);
+    return Array.isArray(parsed) ? parsed : [];
+  } catch {
+    return [];
+  }
+}
+
 /**
  * 发票筛选条件
  */
@@ -72,6 +132,7 @@ export interface InvoiceFilter {
   amountMax?: number;
   keyword?: string;
   category?: string;
+  buyerCheck?: BuyerCheck;
 }
 
 /**
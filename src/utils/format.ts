@@ -1,3 +1,5 @@
+import { InvoiceWarning } from '../types/invoice';
+
 /**
  * 格式化金额
  * @param amount 金额数值
@@ -112,3 +114,51 @@ export function formatTaxNumber(taxNumber: string | undefined | null): string {
   // 每4位添加空格
   return taxNumber.replace(/(.{4})/g, '$1 ').trim();
 }
+
+/** 提示中涉及的字段名称 */
+const WARNING_FIELD_LABELS: Record<string, string> = {
+  invoice_code: '发票代码',
+  invoice_number: '发票号码',
+  invoice_date: '开票日期',
+  check_code: '校验码',
+  amount_without_tax: '不含税金额',
+  tax_amount: '税额',
+  total_amount: '价税合计',
+  buyer_tax_number: '买方税号',
+  seller_tax_number: '卖方税号',
+};
+
+/**
+ * 格式化识别提示为一句说明
+ * @param warning 识别提示
+ */
+export function formatWarning(warning: InvoiceWarning): string {
+  switch (warning.kind) {
+    case 'qr_mismatch':
+      return `${WARNING_FIELD_LABELS[warning.field] ?? warning.field}与二维码不一致：识别为 ${warning.ocr_value}，已采用 ${warning.qr_value}`;
+    case 'duplicate':
+      return `与已有发票重复（ID: ${warning.existing_id}）`;
+    case 'amount_mismatch':
+      return `不含税金额 ${formatAmount(warning.amount_without_tax)} + 税额 ${formatAmount(warning.tax_amount)} 与价税合计 ${formatAmount(warning.total_amount)} 不符`;
+    case 'line_sum_mismatch':
+      return `商品明细合计 ${formatAmount(warning.line_sum)} 与不含税金额 ${formatAmount(warning.expected)} 不符`;
+    case 'negative_amount':
+      return `${WARNING_FIELD_LABELS[warning.field] ?? warning.field}为负数：${formatAmount(warning.amount)}`;
+    case 'zero_total':
+      return '价税合计为零，可能未识别出金额';
+    case 'invalid_tax_rate':
+      return `${warning.row ? `第${warning.row}行` : ''}税率 ${warning.rate} 不是法定税率`;
+    case 'invalid_date':
+      return `开票日期 ${warning.value} 无法识别，已清空`;
+    case 'buyer_mismatch':
+      return `购买方不是本公司：${warning.buyer_name || '-'}（${warning.buyer_tax_number || '-'}）`;
+    case 'invalid_tax_number':
+      return `${WARNING_FIELD_LABELS[warning.field] ?? warning.field} ${warning.value} 校验不通过${
+        warning.suggestions.length > 0 ? `，可能是 ${warning.suggestions.join(' / ')}` : ''
+      }`;
+    case 'tax_number_corrected':
+      return `${WARNING_FIELD_LABELS[warning.field] ?? warning.field}已由 ${warning.original} 自动修正为 ${warning.corrected}`;
+    case 'classify_fallback':
+      return `票种分类接口调用失败（${warning.error}），已按关键字判断票种`;
+  }
+}